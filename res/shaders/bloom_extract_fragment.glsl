@@ -0,0 +1,17 @@
+#version 330
+
+in vec2 frag_tex_coords;
+out vec4 color;
+
+uniform sampler2D scene;
+uniform float threshold;
+
+void main() {
+    vec3 hdr = texture(scene, frag_tex_coords).rgb;
+    float brightness = max(hdr.r, max(hdr.g, hdr.b));
+
+    // Scaled rather than cut off so the bright part keeps its hue
+    float contribution = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+
+    color = vec4(hdr * contribution, 1.0);
+}
@@ -0,0 +1,21 @@
+#version 330
+
+in vec2 frag_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 direction;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec2 texel = direction / vec2(textureSize(image, 0));
+    vec3 result = texture(image, frag_tex_coords).rgb * weights[0];
+
+    for (int i = 1; i < 5; i++) {
+        result += texture(image, frag_tex_coords + texel * float(i)).rgb * weights[i];
+        result += texture(image, frag_tex_coords - texel * float(i)).rgb * weights[i];
+    }
+
+    color = vec4(result, 1.0);
+}
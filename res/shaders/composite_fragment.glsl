@@ -0,0 +1,66 @@
+#version 330
+
+in vec2 frag_tex_coords;
+out vec4 color;
+
+uniform sampler2D scene;
+uniform sampler2D bloom;
+uniform sampler2D lut;
+
+uniform float exposure;
+uniform int tone_mapping;
+uniform float bloom_intensity;
+uniform bool lut_enabled;
+uniform float lut_size;
+uniform float vignette_intensity;
+uniform float vignette_smoothness;
+
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 linear_to_srgb(vec3 linear) {
+    vec3 low = linear * 12.92;
+    vec3 high = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, vec3(lessThanEqual(linear, vec3(0.0031308))));
+}
+
+// The LUT is a horizontal strip of blue slices, each a red/green square
+vec3 grade(vec3 c) {
+    float slice = c.b * (lut_size - 1.0);
+    float slice_low = floor(slice);
+    float slice_high = min(slice_low + 1.0, lut_size - 1.0);
+
+    vec2 uv = (c.rg * (lut_size - 1.0) + 0.5) / vec2(lut_size * lut_size, lut_size);
+    vec3 low = texture(lut, uv + vec2(slice_low / lut_size, 0.0)).rgb;
+    vec3 high = texture(lut, uv + vec2(slice_high / lut_size, 0.0)).rgb;
+
+    return mix(low, high, slice - slice_low);
+}
+
+void main() {
+    vec3 hdr = texture(scene, frag_tex_coords).rgb;
+    hdr += texture(bloom, frag_tex_coords).rgb * bloom_intensity;
+    hdr *= exposure;
+
+    vec3 ldr;
+    if (tone_mapping == 1) {
+        ldr = hdr / (hdr + 1.0);
+    } else if (tone_mapping == 2) {
+        ldr = aces(hdr);
+    } else {
+        ldr = clamp(hdr, 0.0, 1.0);
+    }
+
+    vec3 display = linear_to_srgb(ldr);
+
+    if (lut_enabled) {
+        display = grade(display);
+    }
+
+    vec2 centered = frag_tex_coords - 0.5;
+    float vignette = 1.0 - smoothstep(0.8 - vignette_smoothness, 0.8, length(centered) * vignette_intensity * 2.0);
+    display *= mix(1.0, vignette, step(0.0001, vignette_intensity));
+
+    color = vec4(display, 1.0);
+}
@@ -3,17 +3,71 @@
 in vec3 frag_pos;
 in vec3 frag_normal;
 in vec2 frag_tex_coords;
+in vec4 frag_tangent;
+in vec4 frag_color;
 out vec4 color;
 
 uniform sampler2D tex;
+uniform sampler2DArray tex_array;
+uniform bool tex_is_array;
+uniform float tex_layer;
+// Set for compressed textures stored top row first
+uniform bool tex_flip_v;
+uniform sampler2D normal_map;
+uniform bool normal_map_flip_v;
+uniform bool normal_mapping;
 uniform vec4 light_pos;
+uniform float alpha_cutoff;
+uniform bool alpha_blend;
+
+// Irradiance spherical harmonics, one matrix per color channel
+uniform mat3 ambient_sh_r;
+uniform mat3 ambient_sh_g;
+uniform mat3 ambient_sh_b;
+
+float sh_channel(mat3 sh, vec3 n) {
+    return sh[0][0] * 0.282095
+        + sh[0][1] * 0.488603 * n.y
+        + sh[0][2] * 0.488603 * n.z
+        + sh[1][0] * 0.488603 * n.x
+        + sh[1][1] * 1.092548 * n.x * n.y
+        + sh[1][2] * 1.092548 * n.y * n.z
+        + sh[2][0] * 0.315392 * (3.0 * n.z * n.z - 1.0)
+        + sh[2][1] * 1.092548 * n.x * n.z
+        + sh[2][2] * 0.546274 * (n.x * n.x - n.y * n.y);
+}
+
+vec2 flip_v(vec2 uv, bool flip) {
+    return flip ? vec2(uv.x, 1.0 - uv.y) : uv;
+}
 
 void main() {
-    float ambient = 0.1;
+    vec4 tex_color = tex_is_array
+        ? texture(tex_array, vec3(frag_tex_coords, tex_layer))
+        : texture(tex, flip_v(frag_tex_coords, tex_flip_v));
+    tex_color *= frag_color;
+    if (tex_color.a < alpha_cutoff) {
+        discard;
+    }
 
     vec3 norm = normalize(frag_normal);
+
+    if (normal_mapping) {
+        // Re-orthogonalize since interpolation skews the basis, w holds the bitangent sign
+        vec3 tangent = normalize(frag_tangent.xyz - norm * dot(norm, frag_tangent.xyz));
+        vec3 bitangent = cross(norm, tangent) * frag_tangent.w;
+        vec3 tangent_normal = texture(normal_map, flip_v(frag_tex_coords, normal_map_flip_v)).xyz * 2.0 - 1.0;
+
+        norm = normalize(mat3(tangent, bitangent, norm) * tangent_normal);
+    }
+    vec3 ambient = max(vec3(
+        sh_channel(ambient_sh_r, norm),
+        sh_channel(ambient_sh_g, norm),
+        sh_channel(ambient_sh_b, norm)
+    ), 0.0);
+
     vec3 light_dir = normalize(light_pos.xyz - frag_pos);
     float diffuse = max(dot(norm, light_dir), 0.0);
 
-    color = vec4(texture(tex, frag_tex_coords).xyz * (ambient + diffuse), 1.0);
-}
\ No newline at end of file
+    color = vec4(tex_color.rgb * (ambient + diffuse), alpha_blend ? tex_color.a : 1.0);
+}
@@ -0,0 +1,51 @@
+#version 330
+
+in vec2 frag_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+
+float luma(vec3 c) {
+    return dot(c, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(image, 0));
+
+    vec3 rgb_m = texture(image, frag_tex_coords).rgb;
+    float luma_nw = luma(texture(image, frag_tex_coords + vec2(-1.0, -1.0) * texel).rgb);
+    float luma_ne = luma(texture(image, frag_tex_coords + vec2(1.0, -1.0) * texel).rgb);
+    float luma_sw = luma(texture(image, frag_tex_coords + vec2(-1.0, 1.0) * texel).rgb);
+    float luma_se = luma(texture(image, frag_tex_coords + vec2(1.0, 1.0) * texel).rgb);
+    float luma_m = luma(rgb_m);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Blur along the edge, which runs perpendicular to the luma gradient
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, -SPAN_MAX, SPAN_MAX) * texel;
+
+    vec3 rgb_a = 0.5 * (
+        texture(image, frag_tex_coords + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(image, frag_tex_coords + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(image, frag_tex_coords - dir * 0.5).rgb +
+        texture(image, frag_tex_coords + dir * 0.5).rgb
+    );
+
+    // The wider blur overshot into another edge
+    float luma_b = luma(rgb_b);
+    color = vec4((luma_b < luma_min || luma_b > luma_max) ? rgb_a : rgb_b, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 330
+
+out vec2 frag_tex_coords;
+
+// A single triangle covering the screen, no vertex buffer needed
+void main() {
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+
+    frag_tex_coords = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 330
+
+in vec3 frag_dir;
+out vec4 color;
+
+uniform samplerCube skybox;
+
+void main() {
+    color = vec4(texture(skybox, frag_dir).rgb, 1.0);
+}
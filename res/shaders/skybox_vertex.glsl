@@ -0,0 +1,14 @@
+#version 330
+
+in vec3 position;
+
+out vec3 frag_dir;
+
+uniform mat4 view_rotation_mat;
+
+void main() {
+    frag_dir = position;
+
+    // Keep the skybox on the far plane
+    gl_Position = (view_rotation_mat * vec4(position, 1.0)).xyww;
+}
@@ -3,18 +3,68 @@
 in vec3 position;
 in vec3 normal;
 in vec2 tex_coords;
+in vec4 tangent;
+in vec4 color;
+in uvec4 joints;
+in vec4 weights;
 
 out vec3 frag_pos;
 out vec3 frag_normal;
 out vec2 frag_tex_coords;
+out vec4 frag_tangent;
+out vec4 frag_color;
 
 uniform mat4 camera_mat;
 uniform mat4 model_mat;
+// Offset and size of the atlas region to sample
+uniform vec4 uv_rect;
+
+// Has to match MAX_JOINTS in the renderer
+const int MAX_JOINTS = 128;
+uniform bool skinning;
+uniform mat4 joint_matrices[MAX_JOINTS];
+
+// Has to match MAX_MORPH_TARGETS in the renderer
+const int MAX_MORPH_TARGETS = 32;
+// Position, normal and tangent offsets, one texel each, for every vertex of every target
+uniform sampler2D morph_targets;
+uniform int morph_target_count;
+uniform int morph_vertex_count;
+uniform vec4 morph_weights[MAX_MORPH_TARGETS / 4];
+
+vec3 morph_offset(int target, int attribute) {
+    int index = (target * morph_vertex_count + gl_VertexID) * 3 + attribute;
+    int width = textureSize(morph_targets, 0).x;
+    return texelFetch(morph_targets, ivec2(index % width, index / width), 0).xyz;
+}
 
 void main() {
-    gl_Position = camera_mat * model_mat * vec4(position, 1.0);
+    vec3 morphed_position = position;
+    vec3 morphed_normal = normal;
+    vec3 morphed_tangent = tangent.xyz;
+    for (int i = 0; i < morph_target_count; i++) {
+        float weight = morph_weights[i / 4][i % 4];
+        if (weight != 0.0) {
+            morphed_position += weight * morph_offset(i, 0);
+            morphed_normal += weight * morph_offset(i, 1);
+            morphed_tangent += weight * morph_offset(i, 2);
+        }
+    }
+
+    mat4 skin_mat = mat4(1.0);
+    if (skinning) {
+        skin_mat = weights.x * joint_matrices[joints.x]
+            + weights.y * joint_matrices[joints.y]
+            + weights.z * joint_matrices[joints.z]
+            + weights.w * joint_matrices[joints.w];
+    }
+    mat4 skinned_model_mat = model_mat * skin_mat;
+
+    gl_Position = camera_mat * skinned_model_mat * vec4(morphed_position, 1.0);
     
-    frag_tex_coords = tex_coords;
-    frag_normal = mat3(transpose(inverse(model_mat))) * normal;
-    frag_pos = vec3(model_mat * vec4(position, 1.0));
+    frag_tex_coords = uv_rect.xy + tex_coords * uv_rect.zw;
+    frag_normal = mat3(transpose(inverse(skinned_model_mat))) * morphed_normal;
+    frag_tangent = vec4(mat3(skinned_model_mat) * morphed_tangent, tangent.w);
+    frag_pos = vec3(skinned_model_mat * vec4(morphed_position, 1.0));
+    frag_color = color;
 }
\ No newline at end of file
@@ -0,0 +1,78 @@
+use glam::{Quat, Vec3};
+
+use super::{skeleton::JointPose, track::Track};
+
+// Anything without a track keeps the value it already had
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct TransformTracks {
+    pub translation: Option<Track<Vec3>>,
+    pub rotation: Option<Track<Quat>>,
+    pub scale: Option<Track<Vec3>>,
+}
+
+impl TransformTracks {
+    // The end of the longest track
+    pub fn get_duration(&self) -> f32 {
+        [
+            self.translation.as_ref().map(Track::get_duration),
+            self.rotation.as_ref().map(Track::get_duration),
+            self.scale.as_ref().map(Track::get_duration),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0.0, f32::max)
+    }
+
+    pub fn sample(&self, time: f32, pose: &mut JointPose) {
+        if let Some(translation) = self.translation.as_ref().and_then(|t| t.sample(time)) {
+            pose.translation = translation;
+        }
+        if let Some(rotation) = self.rotation.as_ref().and_then(|t| t.sample(time)) {
+            pose.rotation = rotation;
+        }
+        if let Some(scale) = self.scale.as_ref().and_then(|t| t.sample(time)) {
+            pose.scale = scale;
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct JointChannel {
+    pub joint: usize,
+    pub tracks: TransformTracks,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    pub duration: f32,
+    pub channels: Vec<JointChannel>,
+    pub morph_weights: Option<Track<Vec<f32>>>,
+}
+
+impl AnimationClip {
+    pub fn new(
+        name: Option<String>,
+        channels: Vec<JointChannel>,
+        morph_weights: Option<Track<Vec<f32>>>,
+    ) -> Self {
+        let duration = channels
+            .iter()
+            .map(|channel| channel.tracks.get_duration())
+            .chain(morph_weights.as_ref().map(Track::get_duration))
+            .fold(0.0, f32::max);
+
+        Self {
+            name,
+            duration,
+            channels,
+            morph_weights,
+        }
+    }
+
+    pub fn sample(&self, time: f32, pose: &mut [JointPose]) {
+        for channel in &self.channels {
+            channel.tracks.sample(time, &mut pose[channel.joint]);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+// Remaps the progress between two keyframes, 0 and 1 stay where they are
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Debug)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    // Overshoots before settling
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticOut,
+    BounceOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+
+        let t = t.clamp(0.0, 1.0);
+        // Mirrors an ease in to make the matching ease out
+        let out = |ease: fn(f32) -> f32| 1.0 - ease(1.0 - t);
+        let in_out = |ease: fn(f32) -> f32| {
+            if t < 0.5 {
+                ease(t * 2.0) * 0.5
+            } else {
+                1.0 - ease(2.0 - t * 2.0) * 0.5
+            }
+        };
+
+        let quad = |t: f32| t * t;
+        let cubic = |t: f32| t * t * t;
+        let sine = |t: f32| 1.0 - (t * FRAC_PI_2).cos();
+        let expo = |t: f32| {
+            if t <= 0.0 {
+                0.0
+            } else {
+                2_f32.powf(10.0 * t - 10.0)
+            }
+        };
+        let back = |t: f32| t * t * ((BACK + 1.0) * t - BACK);
+        let bounce = |t: f32| {
+            let t = 1.0 - t;
+            let bounce = if t < 1.0 / 2.75 {
+                7.5625 * t * t
+            } else if t < 2.0 / 2.75 {
+                7.5625 * (t - 1.5 / 2.75).powi(2) + 0.75
+            } else if t < 2.5 / 2.75 {
+                7.5625 * (t - 2.25 / 2.75).powi(2) + 0.9375
+            } else {
+                7.5625 * (t - 2.625 / 2.75).powi(2) + 0.984375
+            };
+            1.0 - bounce
+        };
+
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => quad(t),
+            Self::QuadOut => out(quad),
+            Self::QuadInOut => in_out(quad),
+            Self::CubicIn => cubic(t),
+            Self::CubicOut => out(cubic),
+            Self::CubicInOut => in_out(cubic),
+            Self::SineIn => sine(t),
+            Self::SineOut => out(sine),
+            Self::SineInOut => in_out(sine),
+            Self::ExpoIn => expo(t),
+            Self::ExpoOut => out(expo),
+            Self::ExpoInOut => in_out(expo),
+            Self::BackIn => back(t),
+            Self::BackOut => out(back),
+            Self::BackInOut => in_out(back),
+            Self::ElasticOut => {
+                if t <= 0.0 || t >= 1.0 {
+                    t
+                } else {
+                    2_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+            Self::BounceOut => out(bounce),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 18] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ExpoInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+        Easing::ElasticOut,
+        Easing::BounceOut,
+    ];
+
+    #[test]
+    fn ends_stay_put() {
+        for easing in ALL {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{easing:?}");
+            // Outside of the keyframes is clamped
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{easing:?}");
+            assert_eq!(easing.apply(2.0), easing.apply(1.0), "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn in_out_is_symmetric() {
+        for easing in [
+            Easing::QuadInOut,
+            Easing::CubicInOut,
+            Easing::SineInOut,
+            Easing::ExpoInOut,
+            Easing::BackInOut,
+        ] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-5, "{easing:?}");
+            let (early, late) = (easing.apply(0.2), easing.apply(0.8));
+            assert!((early + late - 1.0).abs() < 1e-5, "{easing:?}");
+        }
+
+        assert!((Easing::QuadIn.apply(0.5) - 0.25).abs() < 1e-5);
+        assert!((Easing::QuadOut.apply(0.5) - 0.75).abs() < 1e-5);
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+    }
+}
@@ -0,0 +1,235 @@
+use std::path::Path;
+
+use glam::{Mat4, Quat, Vec3};
+use gltf::animation::util::ReadOutputs;
+
+use crate::render::{
+    import::{find_mesh_node, open_gltf},
+    ogl_renderer::MAX_JOINTS,
+};
+
+use super::{
+    clip::{AnimationClip, JointChannel, TransformTracks},
+    skeleton::{Joint, JointPose, Skeleton},
+    track::{Interpolation, Track},
+};
+
+// The parent of every node, by index
+fn get_parents(document: &gltf::Document) -> Vec<Option<usize>> {
+    let mut parents = vec![None; document.nodes().len()];
+
+    for node in document.nodes() {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
+    }
+
+    parents
+}
+
+pub fn load_gltf_skeleton(path: &Path) -> Option<Skeleton> {
+    let (document, buffers) = open_gltf(path);
+    let (node, _) = find_mesh_node(&document)?;
+    let skin = node.skin()?;
+
+    let nodes: Vec<_> = document.nodes().collect();
+    let parents = get_parents(&document);
+    let joint_nodes: Vec<_> = skin.joints().map(|joint| joint.index()).collect();
+
+    // Vertices on the joints past the limit would be drawn with whatever the shader reads
+    if joint_nodes.len() > MAX_JOINTS {
+        eprintln!(
+            "Warning: {path:?} has {} joints, only the first {MAX_JOINTS} can be skinned",
+            joint_nodes.len()
+        );
+    }
+
+    let inverse_binds: Vec<_> = skin
+        .reader(|buffer| Some(&buffers[buffer.index()]))
+        .read_inverse_bind_matrices()
+        .map_or_else(
+            || vec![Mat4::IDENTITY; joint_nodes.len()],
+            |matrices| matrices.map(|m| Mat4::from_cols_array_2d(&m)).collect(),
+        );
+
+    let joints = joint_nodes
+        .iter()
+        .zip(inverse_binds)
+        .map(|(node, inverse_bind)| {
+            let mut offset = Mat4::IDENTITY;
+            let mut ancestor = parents[*node];
+            let mut parent = None;
+
+            while let Some(index) = ancestor {
+                parent = joint_nodes.iter().position(|joint| *joint == index);
+                if parent.is_some() {
+                    break;
+                }
+
+                offset = Mat4::from_cols_array_2d(&nodes[index].transform().matrix()) * offset;
+                ancestor = parents[index];
+            }
+
+            let (translation, rotation, scale) = nodes[*node].transform().decomposed();
+
+            Joint {
+                name: nodes[*node].name().map(String::from),
+                parent,
+                offset,
+                inverse_bind,
+                rest: JointPose {
+                    translation: Vec3::from(translation),
+                    rotation: Quat::from_array(rotation),
+                    scale: Vec3::from(scale),
+                },
+            }
+        })
+        .collect();
+
+    Some(Skeleton::new(joints))
+}
+
+fn convert_interpolation(interpolation: gltf::animation::Interpolation) -> Interpolation {
+    match interpolation {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    }
+}
+
+// The tracks of every node the animation moves, by node index
+fn read_transform_tracks(
+    animation: &gltf::Animation,
+    buffers: &[gltf::buffer::Data],
+) -> Vec<(usize, TransformTracks)> {
+    let mut nodes: Vec<(usize, TransformTracks)> = Vec::new();
+
+    for channel in animation.channels() {
+        let node = channel.target().node().index();
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+        let times: Vec<f32> = reader.read_inputs().unwrap().collect();
+        let interpolation = convert_interpolation(channel.sampler().interpolation());
+
+        let index = match nodes.iter().position(|(index, _)| *index == node) {
+            Some(index) => index,
+            None => {
+                nodes.push((node, TransformTracks::default()));
+                nodes.len() - 1
+            }
+        };
+        let tracks = &mut nodes[index].1;
+
+        match reader.read_outputs().unwrap() {
+            ReadOutputs::Translations(values) => {
+                let values = values.map(Vec3::from).collect();
+                tracks.translation = Some(Track::new(interpolation, times, values));
+            }
+            ReadOutputs::Rotations(values) => {
+                let values = values.into_f32().map(Quat::from_array).collect();
+                tracks.rotation = Some(Track::new(interpolation, times, values));
+            }
+            ReadOutputs::Scales(values) => {
+                let values = values.map(Vec3::from).collect();
+                tracks.scale = Some(Track::new(interpolation, times, values));
+            }
+            ReadOutputs::MorphTargetWeights(_) => (),
+        }
+    }
+
+    nodes.retain(|(_, tracks)| *tracks != TransformTracks::default());
+    nodes
+}
+
+// The morph target weights the animation gives the node, one list per keyframe
+fn read_morph_weights(
+    animation: &gltf::Animation,
+    buffers: &[gltf::buffer::Data],
+    node: usize,
+) -> Option<Track<Vec<f32>>> {
+    let channel = animation.channels().find(|channel| {
+        channel.target().node().index() == node
+            && channel.target().property() == gltf::animation::Property::MorphTargetWeights
+    })?;
+
+    let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+    let times: Vec<f32> = reader.read_inputs()?.collect();
+    let interpolation = convert_interpolation(channel.sampler().interpolation());
+
+    let ReadOutputs::MorphTargetWeights(weights) = reader.read_outputs()? else {
+        return None;
+    };
+    let weights: Vec<f32> = weights.into_f32().collect();
+
+    let values_per_keyframe = match interpolation {
+        Interpolation::CubicSpline => 3,
+        _ => 1,
+    };
+    let targets = weights.len() / (times.len() * values_per_keyframe).max(1);
+    let values = weights
+        .chunks_exact(targets.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect();
+
+    Some(Track::new(interpolation, times, values))
+}
+
+// Every animation of the file, with the channels that move joints of the skeleton
+// load_gltf_skeleton loads and the morph target weights of the mesh load_gltf loads. Other
+// node animations are skipped.
+pub fn load_gltf_animations(path: &Path) -> Vec<AnimationClip> {
+    let (document, buffers) = open_gltf(path);
+    let Some((mesh_node, _)) = find_mesh_node(&document) else {
+        return Vec::new();
+    };
+    let joint_nodes: Vec<_> = mesh_node.skin().map_or_else(Vec::new, |skin| {
+        skin.joints().map(|joint| joint.index()).collect()
+    });
+
+    document
+        .animations()
+        .map(|animation| {
+            let channels = read_transform_tracks(&animation, &buffers)
+                .into_iter()
+                .filter_map(|(node, tracks)| {
+                    let joint = joint_nodes.iter().position(|joint| *joint == node)?;
+                    Some(JointChannel { joint, tracks })
+                })
+                .collect();
+
+            AnimationClip::new(
+                animation.name().map(String::from),
+                channels,
+                read_morph_weights(&animation, &buffers, mesh_node.index()),
+            )
+        })
+        .collect()
+}
+
+// The tracks an animation has for one node of the file
+#[derive(PartialEq, Clone, Debug)]
+pub struct NodeAnimation {
+    pub animation: Option<String>,
+    pub node: Option<String>,
+    pub tracks: TransformTracks,
+}
+
+// Every node every animation of the file moves, joints included, to be played on entities
+// with a KeyframeAnimationComponent. KeyframeAnimationComponent::from_gltf picks one by name.
+pub fn load_gltf_node_animations(path: &Path) -> Vec<NodeAnimation> {
+    let (document, buffers) = open_gltf(path);
+    let nodes: Vec<_> = document.nodes().collect();
+
+    document
+        .animations()
+        .flat_map(|animation| {
+            read_transform_tracks(&animation, &buffers)
+                .into_iter()
+                .map(|(node, tracks)| NodeAnimation {
+                    animation: animation.name().map(String::from),
+                    node: nodes[node].name().map(String::from),
+                    tracks,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
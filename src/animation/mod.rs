@@ -0,0 +1,6 @@
+pub mod clip;
+pub mod easing;
+pub mod import;
+pub mod skeleton;
+pub mod track;
+pub mod tween;
@@ -0,0 +1,110 @@
+use glam::{Mat4, Quat, Vec3};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct JointPose {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl JointPose {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn from_mat4(mat: Mat4) -> Self {
+        let (scale, rotation, translation) = mat.to_scale_rotation_translation();
+
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn blend(&self, other: &Self, weight: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, weight),
+            rotation: self.rotation.slerp(other.rotation, weight),
+            scale: self.scale.lerp(other.scale, weight),
+        }
+    }
+
+    pub fn get_mat(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Joint {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    // Nodes between the joint and its parent that aren't joints themselves, or everything
+    // above a joint without a parent
+    pub offset: Mat4,
+    // From mesh space into the space of the joint in its bind pose
+    pub inverse_bind: Mat4,
+    // Relative to the parent, used for anything an animation doesn't touch
+    pub rest: JointPose,
+}
+
+// Joints are kept in the order vertices refer to them, which doesn't have to put parents
+// before their children. Meshes that are only morphed use an empty skeleton.
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    order: Vec<usize>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Self {
+        let depth = |mut joint: usize| {
+            let mut depth = 0;
+            while let Some(parent) = joints[joint].parent {
+                joint = parent;
+                depth += 1;
+            }
+            depth
+        };
+
+        let mut order: Vec<_> = (0..joints.len()).collect();
+        order.sort_by_key(|joint| depth(*joint));
+
+        Self { joints, order }
+    }
+
+    pub fn get_joint_index(&self, name: &str) -> Option<usize> {
+        self.joints
+            .iter()
+            .position(|joint| joint.name.as_deref() == Some(name))
+    }
+
+    pub fn get_rest_pose(&self) -> Vec<JointPose> {
+        self.joints.iter().map(|joint| joint.rest).collect()
+    }
+
+    // Transforms of every joint relative to the mesh, with one pose per joint
+    pub fn get_joint_transforms(&self, pose: &[JointPose]) -> Vec<Mat4> {
+        let mut transforms = vec![Mat4::IDENTITY; self.joints.len()];
+
+        for joint in &self.order {
+            let parent = self.joints[*joint]
+                .parent
+                .map_or(Mat4::IDENTITY, |parent| transforms[parent]);
+            transforms[*joint] = parent * self.joints[*joint].offset * pose[*joint].get_mat();
+        }
+
+        transforms
+    }
+
+    // What the vertex shader multiplies the bind pose positions by
+    pub fn get_skinning_matrices(&self, pose: &[JointPose]) -> Vec<Mat4> {
+        self.get_joint_transforms(pose)
+            .into_iter()
+            .zip(&self.joints)
+            .map(|(transform, joint)| transform * joint.inverse_bind)
+            .collect()
+    }
+}
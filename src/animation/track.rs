@@ -0,0 +1,320 @@
+use std::ops::{Add, Mul};
+
+use glam::{Quat, Vec3};
+
+use super::easing::Easing;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // Hermite spline, with an in and out tangent stored around each value
+    CubicSpline,
+}
+
+pub trait Keyframe: Clone {
+    fn interpolate(a: &Self, b: &Self, t: f32) -> Self;
+
+    fn cubic(
+        value: &Self,
+        out_tangent: &Self,
+        next_value: &Self,
+        next_in_tangent: &Self,
+        t: f32,
+        dt: f32,
+    ) -> Self;
+
+    // The slope through a keyframe, from its neighbours dt apart
+    fn tangent(previous: &Self, next: &Self, dt: f32) -> Self;
+}
+
+fn hermite<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(
+    value: T,
+    out_tangent: T,
+    next_value: T,
+    next_in_tangent: T,
+    t: f32,
+    dt: f32,
+) -> T {
+    let (t2, t3) = (t * t, t * t * t);
+
+    value * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + out_tangent * ((t3 - 2.0 * t2 + t) * dt)
+        + next_value * (-2.0 * t3 + 3.0 * t2)
+        + next_in_tangent * ((t3 - t2) * dt)
+}
+
+impl Keyframe for f32 {
+    fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+
+    fn cubic(value: &Self, out: &Self, next: &Self, next_in: &Self, t: f32, dt: f32) -> Self {
+        hermite(*value, *out, *next, *next_in, t, dt)
+    }
+
+    fn tangent(previous: &Self, next: &Self, dt: f32) -> Self {
+        (next - previous) / dt
+    }
+}
+
+impl Keyframe for Vec3 {
+    fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        a.lerp(*b, t)
+    }
+
+    fn cubic(value: &Self, out: &Self, next: &Self, next_in: &Self, t: f32, dt: f32) -> Self {
+        hermite(*value, *out, *next, *next_in, t, dt)
+    }
+
+    fn tangent(previous: &Self, next: &Self, dt: f32) -> Self {
+        (*next - *previous) / dt
+    }
+}
+
+impl Keyframe for Quat {
+    fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        a.slerp(*b, t)
+    }
+
+    fn cubic(value: &Self, out: &Self, next: &Self, next_in: &Self, t: f32, dt: f32) -> Self {
+        hermite(*value, *out, *next, *next_in, t, dt).normalize()
+    }
+
+    // Taken on the same hemisphere, q and -q are the same rotation
+    fn tangent(previous: &Self, next: &Self, dt: f32) -> Self {
+        let next = if previous.dot(*next) < 0.0 {
+            -*next
+        } else {
+            *next
+        };
+        (next - *previous) * (1.0 / dt)
+    }
+}
+
+// Morph target weights, one per target
+impl Keyframe for Vec<f32> {
+    fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| f32::interpolate(a, b, t))
+            .collect()
+    }
+
+    fn cubic(value: &Self, out: &Self, next: &Self, next_in: &Self, t: f32, dt: f32) -> Self {
+        (0..value.len())
+            .map(|i| hermite(value[i], out[i], next[i], next_in[i], t, dt))
+            .collect()
+    }
+
+    fn tangent(previous: &Self, next: &Self, dt: f32) -> Self {
+        previous
+            .iter()
+            .zip(next)
+            .map(|(previous, next)| f32::tangent(previous, next, dt))
+            .collect()
+    }
+}
+
+// Values over time. Cubic spline tracks store an in tangent, the value and an out tangent
+// for every keyframe, in that order, like glTF does. The easing applies between every pair
+// of keyframes.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Track<T> {
+    pub interpolation: Interpolation,
+    pub easing: Easing,
+    pub times: Vec<f32>,
+    pub values: Vec<T>,
+}
+
+impl<T: Keyframe> Track<T> {
+    pub fn new(interpolation: Interpolation, times: Vec<f32>, values: Vec<T>) -> Self {
+        Self {
+            interpolation,
+            easing: Easing::Linear,
+            times,
+            values,
+        }
+    }
+
+    pub fn step(times: Vec<f32>, values: Vec<T>) -> Self {
+        Self::new(Interpolation::Step, times, values)
+    }
+
+    pub fn linear(times: Vec<f32>, values: Vec<T>) -> Self {
+        Self::new(Interpolation::Linear, times, values)
+    }
+
+    // A cubic spline through the values with Catmull-Rom tangents, flat at the ends
+    pub fn smooth(times: Vec<f32>, values: Vec<T>) -> Self {
+        let last = values.len().saturating_sub(1);
+        let tangent = |i: usize| {
+            if i == 0 || i == last {
+                T::tangent(&values[i], &values[i], 1.0)
+            } else {
+                T::tangent(&values[i - 1], &values[i + 1], times[i + 1] - times[i - 1])
+            }
+        };
+
+        let spline = (0..values.len())
+            .flat_map(|i| [tangent(i), values[i].clone(), tangent(i)])
+            .collect();
+
+        Self::new(Interpolation::CubicSpline, times, spline)
+    }
+
+    // Two keyframes eased from one value to the other
+    pub fn tween(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self::linear(vec![0.0, duration], vec![from, to]).with_easing(easing)
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+
+    fn get_value(&self, keyframe: usize) -> &T {
+        match self.interpolation {
+            Interpolation::CubicSpline => &self.values[keyframe * 3 + 1],
+            _ => &self.values[keyframe],
+        }
+    }
+
+    // Holds the first and last values outside of the keyframes, None for an empty track
+    pub fn sample(&self, time: f32) -> Option<T> {
+        if self.times.is_empty() {
+            return None;
+        }
+
+        let next = self.times.partition_point(|t| *t <= time);
+        if next == 0 {
+            return Some(self.get_value(0).clone());
+        }
+        if next == self.times.len() {
+            return Some(self.get_value(next - 1).clone());
+        }
+
+        let previous = next - 1;
+        let dt = self.times[next] - self.times[previous];
+        let t = self.easing.apply((time - self.times[previous]) / dt);
+
+        Some(match self.interpolation {
+            Interpolation::Step => self.get_value(previous).clone(),
+            Interpolation::Linear => {
+                T::interpolate(self.get_value(previous), self.get_value(next), t)
+            }
+            Interpolation::CubicSpline => T::cubic(
+                &self.values[previous * 3 + 1],
+                &self.values[previous * 3 + 2],
+                &self.values[next * 3 + 1],
+                &self.values[next * 3],
+                t,
+                dt,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_samples(track: &Track<f32>, samples: &[(f32, f32)]) {
+        for (time, value) in samples {
+            let sample = track.sample(*time).unwrap();
+            assert!(
+                (sample - value).abs() < 1e-5,
+                "{time}: {sample} isn't {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn step() {
+        let track = Track::step(vec![0.0, 1.0, 2.0], vec![0.0, 10.0, 20.0]);
+
+        // Each value holds until the next keyframe, and past the ends
+        assert_samples(
+            &track,
+            &[
+                (-1.0, 0.0),
+                (0.0, 0.0),
+                (0.99, 0.0),
+                (1.0, 10.0),
+                (2.0, 20.0),
+                (3.0, 20.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn linear() {
+        let track = Track::linear(vec![0.0, 1.0, 3.0], vec![0.0, 10.0, 0.0]);
+
+        assert_samples(
+            &track,
+            &[
+                (-1.0, 0.0),
+                (0.0, 0.0),
+                (0.5, 5.0),
+                (1.0, 10.0),
+                (2.0, 5.0),
+                (3.0, 0.0),
+                (4.0, 0.0),
+            ],
+        );
+        assert!(Track::<f32>::linear(Vec::new(), Vec::new())
+            .sample(0.0)
+            .is_none());
+    }
+
+    #[test]
+    fn cubic() {
+        let track = Track::smooth(vec![0.0, 1.0, 2.0], vec![0.0, 10.0, 0.0]);
+
+        // Goes through every keyframe, flat at the peak and the ends
+        assert_samples(
+            &track,
+            &[
+                (0.0, 0.0),
+                (0.5, 5.0),
+                (1.0, 10.0),
+                (1.5, 5.0),
+                (2.0, 0.0),
+                (2.5, 0.0),
+            ],
+        );
+        assert!(track.sample(0.9).unwrap() < 10.0);
+        assert!(track.sample(1.1).unwrap() < 10.0);
+
+        // Tangents are per second, so the slope holds with keyframes further apart
+        let track = Track::new(
+            Interpolation::CubicSpline,
+            vec![0.0, 2.0],
+            vec![0.0, 0.0, 1.0, 1.0, 2.0, 0.0],
+        );
+        assert_samples(&track, &[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]);
+    }
+
+    #[test]
+    fn eased() {
+        let track = Track::tween(0.0, 1.0, 2.0, Easing::QuadIn);
+
+        assert_samples(&track, &[(0.0, 0.0), (1.0, 0.25), (2.0, 1.0)]);
+    }
+
+    #[test]
+    fn quaternions_take_the_short_way() {
+        let track = Track::linear(
+            vec![0.0, 1.0],
+            vec![Quat::IDENTITY, -Quat::from_rotation_y(0.5)],
+        );
+
+        let halfway = track.sample(0.5).unwrap();
+        assert!(halfway.angle_between(Quat::from_rotation_y(0.25)) < 1e-4);
+    }
+}
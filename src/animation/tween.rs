@@ -0,0 +1,45 @@
+use super::{easing::Easing, track::Keyframe};
+
+// Eases a single value from one end to the other, for anything a track is overkill for
+#[derive(PartialEq, Clone, Debug)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+    pub time: f32,
+}
+
+impl<T: Keyframe> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing,
+            time: 0.0,
+        }
+    }
+
+    // Returns the value after advancing
+    pub fn advance(&mut self, dt: f32) -> T {
+        self.time = (self.time + dt).min(self.duration);
+        self.get_value()
+    }
+
+    pub fn get_progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.time / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    pub fn get_value(&self) -> T {
+        T::interpolate(&self.from, &self.to, self.easing.apply(self.get_progress()))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.duration
+    }
+}
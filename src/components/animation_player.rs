@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use glam::Mat4;
+
+use crate::animation::{clip::AnimationClip, skeleton::Skeleton};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct AnimationLayer {
+    pub clip: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    pub weight: f32,
+    // Change in weight per second. Layers that fade out to nothing are removed.
+    pub fade: f32,
+}
+
+impl AnimationLayer {
+    pub fn new(clip: usize, looping: bool) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            looping,
+            weight: 1.0,
+            fade: 0.0,
+        }
+    }
+}
+
+// Plays clips on a skeleton. Every layer is sampled and the poses are blended by weight,
+// then the skinning matrices are updated for the renderer.
+pub struct AnimationPlayerComponent {
+    pub skeleton: Arc<Skeleton>,
+    pub clips: Arc<Vec<AnimationClip>>,
+    pub layers: Vec<AnimationLayer>,
+    // Multiplies the speed of every layer
+    pub speed: f32,
+    pub joint_matrices: Vec<Mat4>,
+}
+
+impl AnimationPlayerComponent {
+    pub fn new(skeleton: Arc<Skeleton>, clips: Arc<Vec<AnimationClip>>) -> Self {
+        let joint_matrices = skeleton.get_skinning_matrices(&skeleton.get_rest_pose());
+
+        Self {
+            skeleton,
+            clips,
+            layers: Vec::new(),
+            speed: 1.0,
+            joint_matrices,
+        }
+    }
+
+    pub fn get_clip_index(&self, name: &str) -> Option<usize> {
+        self.clips
+            .iter()
+            .position(|clip| clip.name.as_deref() == Some(name))
+    }
+
+    // Stops everything else
+    pub fn play(&mut self, clip: usize, looping: bool) {
+        self.layers = vec![AnimationLayer::new(clip, looping)];
+    }
+
+    // Fades the playing layers out while the clip fades in
+    pub fn cross_fade(&mut self, clip: usize, looping: bool, duration: f32) {
+        if duration <= 0.0 {
+            self.play(clip, looping);
+            return;
+        }
+
+        for layer in &mut self.layers {
+            layer.fade = -layer.weight.max(f32::EPSILON) / duration;
+        }
+
+        self.layers.push(AnimationLayer {
+            weight: 0.0,
+            fade: 1.0 / duration,
+            ..AnimationLayer::new(clip, looping)
+        });
+    }
+
+    // Plays the clip alongside the other layers at a fixed weight, e.g. to mix walking
+    // and running by speed
+    pub fn blend(&mut self, clip: usize, looping: bool, weight: f32) {
+        self.layers.push(AnimationLayer {
+            weight,
+            ..AnimationLayer::new(clip, looping)
+        });
+    }
+
+    pub fn stop(&mut self) {
+        self.layers.clear();
+    }
+
+    // Whether nothing is playing or every layer is a finished clip that doesn't loop
+    pub fn is_finished(&self) -> bool {
+        self.layers.iter().all(|layer| {
+            let duration = self.clips[layer.clip].duration;
+
+            !layer.looping
+                && if layer.speed * self.speed < 0.0 {
+                    layer.time <= 0.0
+                } else {
+                    layer.time >= duration
+                }
+        })
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        for layer in &mut self.layers {
+            let duration = self.clips[layer.clip].duration;
+            layer.time += dt * layer.speed * self.speed;
+
+            if layer.looping && duration > 0.0 {
+                layer.time = layer.time.rem_euclid(duration);
+            } else {
+                layer.time = layer.time.clamp(0.0, duration);
+            }
+
+            if layer.fade != 0.0 {
+                layer.weight = (layer.weight + layer.fade * dt).min(1.0);
+                if layer.weight >= 1.0 {
+                    layer.fade = 0.0;
+                }
+            }
+        }
+
+        self.layers
+            .retain(|layer| layer.fade >= 0.0 || layer.weight > 0.0);
+
+        self.update_joint_matrices();
+    }
+
+    // Weights are relative, each layer is blended in by its share of the total
+    pub fn update_joint_matrices(&mut self) {
+        let rest = self.skeleton.get_rest_pose();
+        let mut pose = rest.clone();
+        let mut total = 0.0;
+
+        for layer in &self.layers {
+            if layer.weight <= 0.0 {
+                continue;
+            }
+
+            let mut layer_pose = rest.clone();
+            self.clips[layer.clip].sample(layer.time, &mut layer_pose);
+
+            total += layer.weight;
+            let share = layer.weight / total;
+            for (joint, layer_joint) in pose.iter_mut().zip(&layer_pose) {
+                *joint = joint.blend(layer_joint, share);
+            }
+        }
+
+        self.joint_matrices = self.skeleton.get_skinning_matrices(&pose);
+    }
+
+    // Blends the morph target weights of the layers whose clips have them over the given
+    // weights, the same way joints are blended
+    pub fn sample_morph_weights(&self, weights: &mut Vec<f32>) {
+        let mut total = 0.0;
+
+        for layer in &self.layers {
+            if layer.weight <= 0.0 {
+                continue;
+            }
+            let Some(track) = &self.clips[layer.clip].morph_weights else {
+                continue;
+            };
+            let Some(layer_weights) = track.sample(layer.time) else {
+                continue;
+            };
+
+            if weights.len() < layer_weights.len() {
+                weights.resize(layer_weights.len(), 0.0);
+            }
+
+            total += layer.weight;
+            let share = layer.weight / total;
+            for (weight, layer_weight) in weights.iter_mut().zip(layer_weights) {
+                *weight += (layer_weight - *weight) * share;
+            }
+        }
+    }
+}
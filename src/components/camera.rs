@@ -0,0 +1,259 @@
+use brood::entity;
+use glam::{Mat4, Vec3A, Vec4};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+use crate::{components::transform::TransformComponent, geometry::Ray, RenderTargetId};
+
+pub struct CameraComponent {
+    pub projection: Projection,
+    pub viewport: Viewport,
+    pub order: i32,
+    pub clear: ClearSettings,
+    pub target: Option<RenderTargetId>,
+    pub active: bool,
+    pub controller: CameraController,
+}
+
+impl CameraComponent {
+    pub fn new(fov: f32) -> Self {
+        Self::with_projection(Projection::Perspective {
+            fov,
+            near: 0.1,
+            far: None,
+        })
+    }
+
+    pub fn orthographic(height: f32) -> Self {
+        Self::with_projection(Projection::Orthographic {
+            scaling: OrthographicScaling::Height(height),
+            near: 0.1,
+            far: 1000.0,
+        })
+    }
+
+    // Looks down +Z with one world unit per `pixels_per_unit` pixels, for sprites and UI
+    pub fn new_2d(pixels_per_unit: f32) -> Self {
+        Self::with_projection(Projection::Orthographic {
+            scaling: OrthographicScaling::PixelsPerUnit(pixels_per_unit),
+            near: -1000.0,
+            far: 1000.0,
+        })
+    }
+
+    // Orthographic cameras clear to a solid color, a skybox rarely fits 2D
+    pub fn with_projection(projection: Projection) -> Self {
+        Self {
+            projection,
+            viewport: Viewport::FULL,
+            order: 0,
+            clear: ClearSettings {
+                skybox: matches!(projection, Projection::Perspective { .. }),
+                ..Default::default()
+            },
+            target: None,
+            active: true,
+            controller: CameraController::Fixed,
+        }
+    }
+
+    // Takes the viewport size in pixels
+    pub fn get_projection(&self, width: f32, height: f32) -> Mat4 {
+        self.projection.get_mat(width, height)
+    }
+
+    // Returns `None` when the position is outside of the viewport
+    pub fn screen_to_ray(
+        &self,
+        transform: &TransformComponent,
+        position: PhysicalPosition<f32>,
+        target_size: PhysicalSize<u32>,
+    ) -> Option<Ray> {
+        let x = self.viewport.x * target_size.width as f32;
+        let y = self.viewport.y * target_size.height as f32;
+        let width = self.viewport.width * target_size.width as f32;
+        let height = self.viewport.height * target_size.height as f32;
+
+        let ndc_x = (position.x - x) / width * 2.0 - 1.0;
+        let ndc_y = 1.0 - (position.y - y) / height * 2.0;
+
+        if !(-1.0..=1.0).contains(&ndc_x) || !(-1.0..=1.0).contains(&ndc_y) {
+            return None;
+        }
+
+        let inverse = (self.get_projection(width, height) * transform.get_mat().inverse()).inverse();
+        let unproject = |depth: f32| {
+            let point = inverse * Vec4::new(ndc_x, ndc_y, depth, 1.0);
+            Vec3A::from(point.truncate() / point.w)
+        };
+
+        // Points on the near plane and partway in, since an infinite far plane can't be unprojected
+        let near = unproject(0.0);
+        let far = unproject(0.5);
+
+        Some(Ray::new(near, far - near))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    // Infinite far plane when `far` is `None`
+    Perspective {
+        fov: f32,
+        near: f32,
+        far: Option<f32>,
+    },
+    Orthographic {
+        scaling: OrthographicScaling,
+        near: f32,
+        far: f32,
+    },
+}
+
+impl Projection {
+    pub fn get_mat(&self, width: f32, height: f32) -> Mat4 {
+        match *self {
+            Projection::Perspective { fov, near, far } => match far {
+                Some(far) => Mat4::perspective_lh(fov, width / height, near, far),
+                None => Mat4::perspective_infinite_lh(fov, width / height, near),
+            },
+            Projection::Orthographic { scaling, near, far } => {
+                let (half_width, half_height) = match scaling {
+                    OrthographicScaling::Height(size) => {
+                        (size * width / height / 2.0, size / 2.0)
+                    }
+                    OrthographicScaling::PixelsPerUnit(pixels_per_unit) => (
+                        width / pixels_per_unit / 2.0,
+                        height / pixels_per_unit / 2.0,
+                    ),
+                };
+
+                Mat4::orthographic_lh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OrthographicScaling {
+    // World units visible vertically, the width follows the aspect ratio
+    Height(f32),
+    PixelsPerUnit(f32),
+}
+
+// Normalized to the size of the render target, with the origin at the top left
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ClearSettings {
+    pub color: Option<[f32; 4]>,
+    pub depth: bool,
+    pub skybox: bool,
+}
+
+impl Default for ClearSettings {
+    fn default() -> Self {
+        Self {
+            color: Some([0.0, 0.0, 0.0, 1.0]),
+            depth: true,
+            skybox: true,
+        }
+    }
+}
+
+pub enum CameraController {
+    Fixed,
+    FreeFly,
+    Orbit(OrbitController),
+    Follow(FollowController),
+}
+
+pub struct OrbitController {
+    pub target: Vec3A,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Vec3A, distance: f32) -> Self {
+        Self {
+            target,
+            yaw: 0.0,
+            pitch: 0.0,
+            distance,
+            min_distance: 0.5,
+            max_distance: 100.0,
+        }
+    }
+}
+
+// Follows the target entity from the end of a spring arm. The arm is shortened to what a
+// sphere cast from the pivot reaches, so walls don't come between the camera and the target.
+pub struct FollowController {
+    pub target: entity::Identifier,
+    pub offset: Vec3A,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub arm_length: f32,
+    pub min_arm_length: f32,
+    pub max_arm_length: f32,
+    pub stiffness: f32,
+    // Radius of the sphere cast along the arm, roughly how close the camera gets to walls
+    pub probe_radius: f32,
+    // The layers the arm collides with, the target itself is always ignored
+    pub collision_mask: u32,
+    pub current_arm_length: f32,
+    pub current_pivot: Option<Vec3A>,
+}
+
+impl FollowController {
+    pub fn new(target: entity::Identifier, offset: Vec3A, arm_length: f32) -> Self {
+        Self {
+            target,
+            offset,
+            yaw: 0.0,
+            pitch: 0.0,
+            arm_length,
+            min_arm_length: 1.0,
+            max_arm_length: 20.0,
+            stiffness: 10.0,
+            probe_radius: 0.2,
+            collision_mask: u32::MAX,
+            current_arm_length: arm_length,
+            current_pivot: None,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use glam::Vec3A;
+
+use crate::{geometry::TriangleMesh, physics::hull::ConvexHull, render::mesh_data::MeshData};
+
+use super::draw::DrawComponent;
+
+// Shapes are centered on the entity and scaled with it
+#[derive(Clone)]
+pub enum ColliderShape {
+    Sphere { radius: f32 },
+    Box { half_extents: Vec3A },
+    // Along the Y axis, the half height is of the part between the caps
+    Capsule { radius: f32, half_height: f32 },
+    ConvexHull(Arc<ConvexHull>),
+    // Doesn't collide with other triangle meshes, so best left on static and kinematic bodies
+    TriangleMesh(Arc<TriangleMesh>),
+}
+
+impl ColliderShape {
+    pub fn triangle_mesh(data: &MeshData) -> Self {
+        Self::TriangleMesh(Arc::new(TriangleMesh::new(
+            data.positions
+                .iter()
+                .map(|position| (*position).into())
+                .collect(),
+            data.indices.clone(),
+        )))
+    }
+
+    // None when the points are all on a plane
+    pub fn convex_hull(data: &MeshData) -> Option<Self> {
+        let points: Vec<Vec3A> = data.positions.iter().map(|p| (*p).into()).collect();
+        ConvexHull::new(&points).map(|hull| Self::ConvexHull(Arc::new(hull)))
+    }
+
+    // From the mesh the component draws, None when the renderer doesn't keep one
+    pub fn from_draw(draw: &DrawComponent) -> Option<Self> {
+        let shape = draw.inner.get_shape()?;
+
+        Some(Self::TriangleMesh(Arc::new(TriangleMesh::new(
+            shape.positions.clone(),
+            Some(shape.indices.clone()),
+        ))))
+    }
+
+    pub fn convex_hull_from_draw(draw: &DrawComponent) -> Option<Self> {
+        let shape = draw.inner.get_shape()?;
+        ConvexHull::new(&shape.positions).map(|hull| Self::ConvexHull(Arc::new(hull)))
+    }
+}
+
+pub struct ColliderComponent {
+    pub shape: ColliderShape,
+    pub friction: f32,
+    // How much of the speed is kept when bouncing, the higher of the two bodies is used
+    pub restitution: f32,
+    // Bit mask of the layers the collider is on, for queries
+    pub layers: u32,
+}
+
+impl ColliderComponent {
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            friction: 0.5,
+            restitution: 0.0,
+            layers: 1,
+        }
+    }
+
+    pub fn sphere(radius: f32) -> Self {
+        Self::new(ColliderShape::Sphere { radius })
+    }
+
+    pub fn cuboid(half_extents: Vec3A) -> Self {
+        Self::new(ColliderShape::Box { half_extents })
+    }
+
+    pub fn capsule(radius: f32, half_height: f32) -> Self {
+        Self::new(ColliderShape::Capsule {
+            radius,
+            half_height,
+        })
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_layers(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+}
@@ -0,0 +1,250 @@
+use std::{path::Path, sync::Arc};
+
+use glam::{Quat, Vec3};
+
+use crate::animation::{
+    clip::TransformTracks, easing::Easing, import::load_gltf_node_animations, skeleton::JointPose,
+    track::Track,
+};
+
+use super::transform::TransformComponent;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LoopMode {
+    Once,
+    Loop,
+    // Plays forwards then backwards
+    PingPong,
+}
+
+// Drives the transform of its entity with keyframe tracks, from glTF or made in code.
+// Whatever has no track is left for other systems to move.
+pub struct KeyframeAnimationComponent {
+    pub tracks: Arc<TransformTracks>,
+    pub time: f32,
+    pub speed: f32,
+    pub loop_mode: LoopMode,
+    pub playing: bool,
+}
+
+impl KeyframeAnimationComponent {
+    pub fn new(tracks: Arc<TransformTracks>, loop_mode: LoopMode) -> Self {
+        Self {
+            tracks,
+            time: 0.0,
+            speed: 1.0,
+            loop_mode,
+            playing: true,
+        }
+    }
+
+    // The first animation of the file that moves the named node, None when none does
+    pub fn from_gltf(path: &Path, node: &str, loop_mode: LoopMode) -> Option<Self> {
+        let animation = load_gltf_node_animations(path)
+            .into_iter()
+            .find(|animation| animation.node.as_deref() == Some(node))?;
+
+        Some(Self::new(Arc::new(animation.tracks), loop_mode))
+    }
+
+    pub fn translate(from: Vec3, to: Vec3, duration: f32, easing: Easing) -> Self {
+        Self::new(
+            Arc::new(TransformTracks {
+                translation: Some(Track::tween(from, to, duration, easing)),
+                ..Default::default()
+            }),
+            LoopMode::Once,
+        )
+    }
+
+    pub fn rotate(from: Quat, to: Quat, duration: f32, easing: Easing) -> Self {
+        Self::new(
+            Arc::new(TransformTracks {
+                rotation: Some(Track::tween(from, to, duration, easing)),
+                ..Default::default()
+            }),
+            LoopMode::Once,
+        )
+    }
+
+    pub fn scale(from: Vec3, to: Vec3, duration: f32, easing: Easing) -> Self {
+        Self::new(
+            Arc::new(TransformTracks {
+                scale: Some(Track::tween(from, to, duration, easing)),
+                ..Default::default()
+            }),
+            LoopMode::Once,
+        )
+    }
+
+    // Tweens the whole transform
+    pub fn tween(
+        from: &TransformComponent,
+        to: &TransformComponent,
+        duration: f32,
+        easing: Easing,
+    ) -> Self {
+        Self::new(
+            Arc::new(TransformTracks {
+                translation: Some(Track::tween(
+                    from.translation.into(),
+                    to.translation.into(),
+                    duration,
+                    easing,
+                )),
+                rotation: Some(Track::tween(from.rotation, to.rotation, duration, easing)),
+                scale: Some(Track::tween(
+                    from.scale.into(),
+                    to.scale.into(),
+                    duration,
+                    easing,
+                )),
+            }),
+            LoopMode::Once,
+        )
+    }
+
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.loop_mode == LoopMode::Once && self.time >= self.tracks.get_duration()
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+
+        let duration = self.tracks.get_duration();
+        self.time += dt * self.speed;
+
+        self.time = match self.loop_mode {
+            LoopMode::Once => self.time.clamp(0.0, duration),
+            _ if duration <= 0.0 => 0.0,
+            LoopMode::Loop => self.time.rem_euclid(duration),
+            LoopMode::PingPong => self.time.rem_euclid(duration * 2.0),
+        };
+    }
+
+    // Where in the tracks the current time is, after ping-ponging
+    pub fn get_sample_time(&self) -> f32 {
+        let duration = self.tracks.get_duration();
+
+        match self.loop_mode {
+            LoopMode::PingPong if self.time > duration => duration * 2.0 - self.time,
+            _ => self.time,
+        }
+    }
+
+    pub fn apply(&self, transform: &mut TransformComponent) {
+        let mut pose = JointPose {
+            translation: transform.translation.into(),
+            rotation: transform.rotation,
+            scale: transform.scale.into(),
+        };
+        self.tracks.sample(self.get_sample_time(), &mut pose);
+
+        transform.translation = pose.translation.into();
+        transform.rotation = pose.rotation;
+        transform.scale = pose.scale.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn get_x(animation: &KeyframeAnimationComponent) -> f32 {
+        let mut transform = TransformComponent::new();
+        animation.apply(&mut transform);
+        transform.translation.x
+    }
+
+    #[test]
+    fn advance_wraps() {
+        let slide =
+            || KeyframeAnimationComponent::translate(Vec3::ZERO, Vec3::X, 1.0, Easing::Linear);
+
+        let mut once = slide();
+        once.advance(1.5);
+        assert_eq!(once.time, 1.0);
+        assert!(once.is_finished());
+
+        let mut looping = slide().with_loop_mode(LoopMode::Loop);
+        looping.advance(1.25);
+        assert!((looping.time - 0.25).abs() < 1e-5);
+        // Playing backwards wraps the other way
+        looping.speed = -1.0;
+        looping.advance(0.5);
+        assert!((looping.time - 0.75).abs() < 1e-5);
+        assert!(!looping.is_finished());
+
+        let mut paused = slide();
+        paused.playing = false;
+        paused.advance(0.5);
+        assert_eq!(paused.time, 0.0);
+    }
+
+    #[test]
+    fn ping_pong_reverses() {
+        let mut animation =
+            KeyframeAnimationComponent::translate(Vec3::ZERO, Vec3::X, 1.0, Easing::Linear)
+                .with_loop_mode(LoopMode::PingPong);
+
+        animation.advance(0.75);
+        assert!((get_x(&animation) - 0.75).abs() < 1e-5);
+
+        // On the way back
+        animation.advance(0.5);
+        assert!((animation.get_sample_time() - 0.75).abs() < 1e-5);
+        assert!((get_x(&animation) - 0.75).abs() < 1e-5);
+
+        // And forwards again
+        animation.advance(1.0);
+        assert!((animation.get_sample_time() - 0.25).abs() < 1e-5);
+        assert!((get_x(&animation) - 0.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn node_animation_from_gltf() {
+        let directory = std::env::temp_dir().join("node_animation_from_gltf");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("slide.gltf");
+        // Moves the node named box from the origin to x = 2 over a second
+        fs::write(
+            &path,
+            r#"{
+                "asset": {"version": "2.0"},
+                "buffers": [{"byteLength": 32, "uri": "data:application/octet-stream;base64,AAAAAAAAgD8AAAAAAAAAAAAAAAAAAABAAAAAAAAAAAA="}],
+                "bufferViews": [
+                    {"buffer": 0, "byteLength": 8},
+                    {"buffer": 0, "byteOffset": 8, "byteLength": 24}
+                ],
+                "accessors": [
+                    {"bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0], "max": [1]},
+                    {"bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC3"}
+                ],
+                "animations": [{
+                    "channels": [{"sampler": 0, "target": {"node": 0, "path": "translation"}}],
+                    "samplers": [{"input": 0, "output": 1}]
+                }],
+                "nodes": [{"name": "box"}],
+                "scenes": [{"nodes": [0]}]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(KeyframeAnimationComponent::from_gltf(&path, "sphere", LoopMode::Once).is_none());
+
+        let mut animation =
+            KeyframeAnimationComponent::from_gltf(&path, "box", LoopMode::Once).unwrap();
+        assert_eq!(animation.tracks.get_duration(), 1.0);
+        animation.advance(0.25);
+        assert!((get_x(&animation) - 0.5).abs() < 1e-5);
+    }
+}
@@ -1,8 +1,28 @@
 use brood::Registry;
 
-use self::{draw::DrawComponent, transform::TransformComponent};
+use self::{
+    animation_player::AnimationPlayerComponent, camera::CameraComponent,
+    collider::ColliderComponent, draw::DrawComponent,
+    keyframe_animation::KeyframeAnimationComponent, morph_weights::MorphWeightsComponent,
+    rigid_body::RigidBodyComponent, transform::TransformComponent,
+};
 
+pub mod animation_player;
+pub mod camera;
+pub mod collider;
 pub mod draw;
+pub mod keyframe_animation;
+pub mod morph_weights;
+pub mod rigid_body;
 pub mod transform;
 
-pub type Registry = Registry!(DrawComponent, TransformComponent);
+pub type Registry = Registry!(
+    DrawComponent,
+    TransformComponent,
+    CameraComponent,
+    AnimationPlayerComponent,
+    KeyframeAnimationComponent,
+    MorphWeightsComponent,
+    RigidBodyComponent,
+    ColliderComponent
+);
@@ -0,0 +1,27 @@
+use std::path::Path;
+
+use crate::render::import;
+
+// How much of each morph target of the mesh is blended in, usually between 0 and 1.
+// Targets without a weight stay at zero.
+pub struct MorphWeightsComponent {
+    pub weights: Vec<f32>,
+}
+
+impl MorphWeightsComponent {
+    pub fn new(weights: Vec<f32>) -> Self {
+        Self { weights }
+    }
+
+    // Starts with the default weights the file has for the mesh load_gltf loads
+    pub fn from_gltf(path: &Path) -> Self {
+        Self::new(import::load_gltf_morph_weights(path))
+    }
+
+    pub fn set_weight(&mut self, target: usize, weight: f32) {
+        if self.weights.len() <= target {
+            self.weights.resize(target + 1, 0.0);
+        }
+        self.weights[target] = weight;
+    }
+}
@@ -0,0 +1,79 @@
+use glam::Vec3A;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BodyType {
+    Dynamic,
+    // Moved by its velocity only, pushes dynamic bodies without being pushed back
+    Kinematic,
+    Static,
+}
+
+// Colliders without a rigid body are static
+pub struct RigidBodyComponent {
+    pub body_type: BodyType,
+    pub mass: f32,
+    pub linear_velocity: Vec3A,
+    pub angular_velocity: Vec3A,
+    // Fraction of the velocity lost per second, roughly
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub gravity_scale: f32,
+    // Applied over the next physics steps, then cleared
+    pub force: Vec3A,
+    pub torque: Vec3A,
+}
+
+impl RigidBodyComponent {
+    fn new(body_type: BodyType, mass: f32) -> Self {
+        Self {
+            body_type,
+            mass,
+            linear_velocity: Vec3A::ZERO,
+            angular_velocity: Vec3A::ZERO,
+            linear_damping: 0.01,
+            angular_damping: 0.05,
+            gravity_scale: 1.0,
+            force: Vec3A::ZERO,
+            torque: Vec3A::ZERO,
+        }
+    }
+
+    pub fn dynamic(mass: f32) -> Self {
+        Self::new(BodyType::Dynamic, mass)
+    }
+
+    pub fn kinematic() -> Self {
+        Self::new(BodyType::Kinematic, 0.0)
+    }
+
+    pub fn fixed() -> Self {
+        Self::new(BodyType::Static, 0.0)
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec3A) -> Self {
+        self.linear_velocity = velocity;
+        self
+    }
+
+    // Zero for anything that isn't pushed around
+    pub fn get_inverse_mass(&self) -> f32 {
+        if self.body_type == BodyType::Dynamic && self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    pub fn add_force(&mut self, force: Vec3A) {
+        self.force += force;
+    }
+
+    pub fn add_torque(&mut self, torque: Vec3A) {
+        self.torque += torque;
+    }
+
+    // An instant change in momentum, through the center
+    pub fn add_impulse(&mut self, impulse: Vec3A) {
+        self.linear_velocity += impulse * self.get_inverse_mass();
+    }
+}
@@ -34,7 +34,11 @@ impl TransformComponent {
         }
     }
 
+    pub fn get_mat(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale.into(), self.rotation, self.translation.into())
+    }
+
     pub fn get_mat_array(&self) -> [[f32; 4]; 4] {
-        Mat4::from_scale_rotation_translation(self.scale.into(), self.rotation, self.translation.into()).to_cols_array_2d()
+        self.get_mat().to_cols_array_2d()
     }
 }
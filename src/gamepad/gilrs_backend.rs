@@ -0,0 +1,83 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use super::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadEventKind, GamepadId};
+
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Option<Self> {
+        Gilrs::new().ok().map(|gilrs| Self { gilrs })
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        // Skip over events that have no equivalent instead of ending the poll early
+        while let Some(event) = self.gilrs.next_event() {
+            let kind = match event.event {
+                EventType::Connected => Some(GamepadEventKind::Connected),
+                EventType::Disconnected => Some(GamepadEventKind::Disconnected),
+                EventType::ButtonPressed(button, _) => {
+                    convert_button(button).map(GamepadEventKind::ButtonPressed)
+                }
+                EventType::ButtonReleased(button, _) => {
+                    convert_button(button).map(GamepadEventKind::ButtonReleased)
+                }
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => Some(
+                    GamepadEventKind::AxisChanged(GamepadAxis::LeftTrigger, value),
+                ),
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => Some(
+                    GamepadEventKind::AxisChanged(GamepadAxis::RightTrigger, value),
+                ),
+                EventType::AxisChanged(axis, value, _) => {
+                    convert_axis(axis).map(|axis| GamepadEventKind::AxisChanged(axis, value))
+                }
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                return Some(GamepadEvent {
+                    id: GamepadId(event.id.into()),
+                    kind,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+fn convert_button(button: Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+fn convert_axis(axis: Axis) -> Option<GamepadAxis> {
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        _ => return None,
+    })
+}
@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+
+use super::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadEventKind, GamepadId};
+
+#[derive(Default)]
+pub struct MockGamepadBackend {
+    events: VecDeque<GamepadEvent>,
+}
+
+impl MockGamepadBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: GamepadEvent) {
+        self.events.push_back(event);
+    }
+
+    pub fn connect(&mut self, id: GamepadId) {
+        self.push(GamepadEvent {
+            id,
+            kind: GamepadEventKind::Connected,
+        });
+    }
+
+    pub fn disconnect(&mut self, id: GamepadId) {
+        self.push(GamepadEvent {
+            id,
+            kind: GamepadEventKind::Disconnected,
+        });
+    }
+
+    pub fn press(&mut self, id: GamepadId, button: GamepadButton) {
+        self.push(GamepadEvent {
+            id,
+            kind: GamepadEventKind::ButtonPressed(button),
+        });
+    }
+
+    pub fn release(&mut self, id: GamepadId, button: GamepadButton) {
+        self.push(GamepadEvent {
+            id,
+            kind: GamepadEventKind::ButtonReleased(button),
+        });
+    }
+
+    pub fn set_axis(&mut self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.push(GamepadEvent {
+            id,
+            kind: GamepadEventKind::AxisChanged(axis, value),
+        });
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        self.events.pop_front()
+    }
+}
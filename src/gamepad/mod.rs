@@ -0,0 +1,133 @@
+use bitvec::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gilrs")]
+pub mod gilrs_backend;
+pub mod mock_backend;
+
+pub trait GamepadBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent>;
+}
+
+pub struct NullGamepadBackend;
+
+impl GamepadBackend for NullGamepadBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        None
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GamepadEvent {
+    pub id: GamepadId,
+    pub kind: GamepadEventKind,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadEventKind {
+    Connected,
+    Disconnected,
+    ButtonPressed(GamepadButton),
+    ButtonReleased(GamepadButton),
+    AxisChanged(GamepadAxis, f32),
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GamepadStick {
+    Left,
+    Right,
+}
+
+const AXIS_COUNT: usize = 6;
+
+pub struct GamepadState {
+    pub pressed: BitArray<[u32; 1], Lsb0>,
+    pub just_pressed: BitArray<[u32; 1], Lsb0>,
+    pub just_released: BitArray<[u32; 1], Lsb0>,
+    pub axes: [f32; AXIS_COUNT],
+}
+
+impl GamepadState {
+    pub fn new() -> Self {
+        Self {
+            pressed: BitArray::new([0]),
+            just_pressed: BitArray::new([0]),
+            just_released: BitArray::new([0]),
+            axes: [0.0; AXIS_COUNT],
+        }
+    }
+
+    pub fn event(&mut self, kind: &GamepadEventKind) {
+        match kind {
+            GamepadEventKind::ButtonPressed(button) => {
+                if !self.pressed[*button as usize] {
+                    self.pressed.set(*button as usize, true);
+                    self.just_pressed.set(*button as usize, true);
+                }
+            }
+            GamepadEventKind::ButtonReleased(button) => {
+                if self.pressed[*button as usize] {
+                    self.pressed.set(*button as usize, false);
+                    self.just_released.set(*button as usize, true);
+                }
+            }
+            GamepadEventKind::AxisChanged(axis, value) => {
+                self.axes[*axis as usize] = *value;
+            }
+            GamepadEventKind::Connected | GamepadEventKind::Disconnected => {}
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.just_pressed.fill(false);
+        self.just_released.fill(false);
+    }
+}
+
+impl Default for GamepadState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
@@ -0,0 +1,227 @@
+use glam::{Mat4, Vec3, Vec3A};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ray {
+    pub origin: Vec3A,
+    pub direction: Vec3A,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3A, direction: Vec3A) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3A {
+        self.origin + self.direction * distance
+    }
+
+    // The direction isn't renormalized, so distances along the result are still in the original space
+    pub fn transform(&self, mat: &Mat4) -> Self {
+        Self {
+            origin: mat.transform_point3a(self.origin),
+            direction: mat.transform_vector3a(self.direction),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Vec3A,
+    pub max: Vec3A,
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        min: Vec3A::INFINITY,
+        max: Vec3A::NEG_INFINITY,
+    };
+
+    pub fn new(min: Vec3A, max: Vec3A) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3A>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |aabb, point| Self {
+            min: aabb.min.min(point),
+            max: aabb.max.max(point),
+        })
+    }
+
+    pub fn center(&self) -> Vec3A {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3A {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn transform(&self, mat: &Mat4) -> Self {
+        let center = mat.transform_point3a(self.center());
+        let half_extents = self.half_extents();
+        let extents = mat.x_axis.truncate().abs() * half_extents.x
+            + mat.y_axis.truncate().abs() * half_extents.y
+            + mat.z_axis.truncate().abs() * half_extents.z;
+
+        Self {
+            min: center - Vec3A::from(extents),
+            max: center + Vec3A::from(extents),
+        }
+    }
+
+    // Slab test, returns the distance to the entry point or zero when starting inside
+    pub fn ray_intersection(&self, ray: &Ray, max_distance: f32) -> Option<f32> {
+        // Parallel axes would give 0 * inf = NaN on the edge of their slab, and only rule
+        // the box out when the origin is outside the slab
+        let parallel = ray.direction.cmpeq(Vec3A::ZERO);
+        if (parallel & (ray.origin.cmplt(self.min) | ray.origin.cmpgt(self.max))).any() {
+            return None;
+        }
+
+        let inv_dir = ray.direction.recip();
+        let t1 = (self.min - ray.origin) * inv_dir;
+        let t2 = (self.max - ray.origin) * inv_dir;
+
+        let near = Vec3A::select(parallel, Vec3A::NEG_INFINITY, t1.min(t2));
+        let far = Vec3A::select(parallel, Vec3A::INFINITY, t1.max(t2));
+        let t_min = near.max_element().max(0.0);
+        let t_max = far.min_element().min(max_distance);
+
+        (t_min <= t_max).then_some(t_min)
+    }
+}
+
+// Möller–Trumbore
+pub fn ray_triangle_intersection(ray: &Ray, a: Vec3A, b: Vec3A, c: Vec3A) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = ray.direction.cross(edge2);
+    let det = edge1.dot(p);
+
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inv_det = det.recip();
+    let s = ray.origin - a;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = ray.direction.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    (t >= 0.0).then_some(t)
+}
+
+pub struct TriangleMesh {
+    pub positions: Vec<Vec3A>,
+    pub indices: Vec<u32>,
+    pub bounds: Aabb,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vec3A>, indices: Option<Vec<u32>>) -> Self {
+        let indices = indices.unwrap_or_else(|| (0..positions.len() as u32).collect());
+
+        Self {
+            bounds: Aabb::from_points(positions.iter().copied()),
+            positions,
+            indices,
+        }
+    }
+
+    // Keeps the indices, for meshes that only moved
+    pub fn set_positions(&mut self, positions: &[Vec3]) {
+        self.positions.clear();
+        self.positions
+            .extend(positions.iter().map(|position| Vec3A::from(*position)));
+        self.bounds = Aabb::from_points(self.positions.iter().copied());
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3A; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|i| {
+            [
+                self.positions[i[0] as usize],
+                self.positions[i[1] as usize],
+                self.positions[i[2] as usize],
+            ]
+        })
+    }
+
+    // Returns the distance and the normal of the closest hit. The normal faces the ray, since
+    // the winding of triangles can't be relied on.
+    pub fn ray_intersection(&self, ray: &Ray, max_distance: f32) -> Option<(f32, Vec3A)> {
+        self.bounds.ray_intersection(ray, max_distance)?;
+
+        self.triangles()
+            .filter_map(|[a, b, c]| {
+                ray_triangle_intersection(ray, a, b, c)
+                    .filter(|t| *t <= max_distance)
+                    .map(|t| {
+                        let normal = (b - a).cross(c - a).normalize_or_zero();
+                        let facing = normal.dot(ray.direction) > 0.0;
+                        (t, if facing { -normal } else { normal })
+                    })
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_aligned_ray_on_slab_edge() {
+        let aabb = Aabb::new(Vec3A::ZERO, Vec3A::ONE);
+
+        // 0 * inf on the x and y slabs
+        let ray = Ray::new(Vec3A::new(1.0, 0.0, -2.0), Vec3A::Z);
+        assert_eq!(aabb.ray_intersection(&ray, 10.0), Some(2.0));
+
+        let ray = Ray::new(Vec3A::new(1.5, 0.5, -2.0), Vec3A::Z);
+        assert_eq!(aabb.ray_intersection(&ray, 10.0), None);
+    }
+
+    #[test]
+    fn mesh_normals_face_the_ray() {
+        // The -Z face of the built-in cube is wound inwards
+        let cube = crate::render::shapes::generate(&crate::Mesh::Cube)
+            .unwrap()
+            .get_triangle_mesh();
+
+        for direction in [
+            Vec3A::X,
+            Vec3A::NEG_X,
+            Vec3A::Y,
+            Vec3A::NEG_Y,
+            Vec3A::Z,
+            Vec3A::NEG_Z,
+        ] {
+            let ray = Ray::new(-direction * 5.0, direction);
+            let (distance, normal) = cube.ray_intersection(&ray, 10.0).unwrap();
+
+            assert!((distance - 4.5).abs() < 1e-5);
+            assert!(normal.abs_diff_eq(-direction, 1e-5));
+        }
+    }
+}
@@ -1,37 +1,79 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+pub mod animation;
 pub mod components;
+pub mod gamepad;
+pub mod geometry;
+pub mod physics;
+pub mod picking;
+pub mod recording;
 pub mod render;
 pub mod resources;
 pub mod systems;
 
-use std::{path::Path, time::{Duration, Instant}};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use brood::{entity, resources, schedule, system::schedule::task, World};
+use brood::{entity, query::filter, resources, result, Query, Views, World};
 use glam::{Mat4, Quat, Vec3, Vec4};
 
+use recording::InputRecording;
 use resources::{
-    camera::CameraResource,
+    anti_aliasing::AntiAliasingResource,
     input::InputResource,
+    physics::PhysicsResource,
+    picking::PickingResource,
+    post_process::PostProcessResource,
     time::TimerResource,
     ExitResource, Resources, SunResource,
 };
 use simple_moving_average::{SingleSumSMA, SMA};
-use systems::{camera_system::CameraSystem, close_system::CloseSystem, spin_system::SpinCube};
+use systems::picking_system::PickingSystem;
 use winit::{
+    dpi::PhysicalSize,
     event::{Event, WindowEvent},
     event_loop::EventLoopBuilder,
     window::{CursorGrabMode, WindowAttributes},
 };
 
-use components::{transform::TransformComponent, Registry};
-use render::{ogl_renderer::OglRenderer, *};
+use components::{
+    camera::{CameraComponent, CameraController},
+    transform::TransformComponent,
+    Registry,
+};
+use gamepad::{GamepadBackend, NullGamepadBackend};
+use render::{headless_renderer::HeadlessRenderer, ogl_renderer::OglRenderer, *};
 
 const FULLSCREEN: bool = true;
 const TARGET_FRAMERATE: f64 = 360.0;
 const TARGET_FRAMETIME: Duration = Duration::from_nanos((1000000000_f64/TARGET_FRAMERATE) as u64);
+const MSAA_SAMPLES: u32 = 4;
+const FXAA: bool = false;
 
 fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut record_path = None;
+    let mut replay_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => record_path = args.next().map(PathBuf::from),
+            "--replay" => replay_path = args.next().map(PathBuf::from),
+            _ => {
+                eprintln!("Unknown argument {arg}");
+                eprintln!("Usage: idkgameengine [--record <file>] [--replay <file>]");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = replay_path {
+        replay(&path);
+        return;
+    }
+
     let event_loop = EventLoopBuilder::new()
         .build()
         .expect("Event loop didn't build");
@@ -41,8 +83,23 @@ fn main() {
 
     let mut renderer: Box<dyn Renderer> = Box::new(OglRenderer::new(&window));
 
+    #[cfg(feature = "gilrs")]
+    let mut gamepad_backend: Box<dyn GamepadBackend> =
+        match gamepad::gilrs_backend::GilrsBackend::new() {
+            Some(backend) => Box::new(backend),
+            None => Box::new(NullGamepadBackend),
+        };
+    #[cfg(not(feature = "gilrs"))]
+    let mut gamepad_backend: Box<dyn GamepadBackend> = Box::new(NullGamepadBackend);
+
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
+    let mut world = create_world(renderer.as_mut(), window.inner_size(), window.has_focus());
+
+    if record_path.is_some() {
+        world.get_mut::<InputResource, _>().start_recording();
+    }
+
     if FULLSCREEN {
         window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
         window.set_cursor_visible(false);
@@ -52,15 +109,142 @@ fn main() {
             .unwrap();
     }
 
+    let mut schedule = systems::schedule();
+
+    let mut average_dt = SingleSumSMA::<_, _, 50>::from_zero(Duration::ZERO);
+    let mut next_frame_start_instant = Instant::now();
+
+    let mut ime_allowed = false;
+    let mut ime_cursor_area = None;
+
+    event_loop.run(move |event, window_target| match event {
+        Event::DeviceEvent { event, .. } => {
+            world.get_mut::<InputResource, _>().device_event(&event);
+        }
+        Event::WindowEvent { event, .. } => {
+            world.get_mut::<InputResource, _>().window_event(&event);
+
+            if event == WindowEvent::CloseRequested {
+                window_target.exit();
+            }
+        }
+        Event::AboutToWait => {
+            if Instant::now() >= next_frame_start_instant {
+                let start = Instant::now();
+
+                world
+                    .get_mut::<InputResource, _>()
+                    .poll_gamepads(gamepad_backend.as_mut());
+
+                run_frame(&mut world, &mut schedule, renderer.as_mut());
+
+                if world.get::<ExitResource, _>().0 {
+                    window_target.exit();
+                }
+
+                let input = world.get::<InputResource, _>();
+                if input.is_text_input_active() != ime_allowed {
+                    ime_allowed = input.is_text_input_active();
+                    window.set_ime_allowed(ime_allowed);
+                }
+                if input.get_ime_cursor_area() != ime_cursor_area {
+                    ime_cursor_area = input.get_ime_cursor_area();
+                    if let Some((position, size)) = ime_cursor_area {
+                        window.set_ime_cursor_area(position, size);
+                    }
+                }
+
+                world.get_mut::<TimerResource, _>().tick();
+                let dt = world.get::<TimerResource, _>().get_dt();
+                world.get_mut::<InputResource, _>().tick(dt);
+
+                average_dt.add_sample(world.get::<TimerResource, _>().get_dt());
+                println!("{:.0} FPS", average_dt.get_average().as_secs_f32().recip());
+
+                let frametime = Instant::now() - start;
+
+                next_frame_start_instant = Instant::now() + TARGET_FRAMETIME - frametime;
+            }
+        }
+        Event::LoopExiting => {
+            if let Some(path) = &record_path {
+                if let Some(mut recording) = world.get_mut::<InputResource, _>().stop_recording() {
+                    recording.save(path).unwrap();
+                }
+
+                print_state(&mut world);
+            }
+        }
+        _ => (),
+    }).unwrap();
+}
+
+// The part of a frame between its input and the timers ticking, shared with replays
+fn run_frame(
+    world: &mut World<Registry, Resources>,
+    schedule: &mut systems::Schedule,
+    renderer: &mut dyn Renderer,
+) {
+    world.run_system(&mut PickingSystem);
+    world.run_schedule(schedule);
+
+    renderer.render(world);
+}
+
+fn replay(path: &Path) {
+    let mut world = replay_recording(InputRecording::load(path).unwrap());
+
+    print_state(&mut world);
+}
+
+fn replay_recording(recording: InputRecording) -> World<Registry, Resources> {
+    let mut renderer = HeadlessRenderer::default();
+    let mut world = create_world(&mut renderer, recording.window_size, recording.focused);
+    let mut schedule = systems::schedule();
+
+    for frame in recording.frames {
+        for event in frame.events {
+            world.get_mut::<InputResource, _>().input_event(event);
+        }
+
+        run_frame(&mut world, &mut schedule, &mut renderer);
+
+        world.get_mut::<TimerResource, _>().tick_with(frame.dt);
+        world.get_mut::<InputResource, _>().tick(frame.dt);
+
+        if world.get::<ExitResource, _>().0 {
+            break;
+        }
+    }
+
+    world
+}
+
+fn create_world(
+    renderer: &mut dyn Renderer,
+    window_size: PhysicalSize<u32>,
+    focused: bool,
+) -> World<Registry, Resources> {
     let mut world = World::<Registry, Resources>::with_resources(resources!(
-        CameraResource::new(
-            60_f32.to_radians(),
-            window.inner_size().width as f32 / window.inner_size().height as f32
-        ),
         TimerResource::new(Duration::from_millis(100)),
-        InputResource::new(window.has_focus()),
+        InputResource::new(focused, window_size),
         ExitResource(false),
         SunResource(Vec4::new(1.2, 1.0, 2.0, 1.0)),
+        PickingResource::default(),
+        PostProcessResource::default(),
+        AntiAliasingResource {
+            msaa_samples: MSAA_SAMPLES,
+            fxaa: FXAA,
+        },
+        PhysicsResource::default(),
+    ));
+
+    world.insert(entity!(
+        TransformComponent::new(),
+        CameraComponent {
+            controller: CameraController::FreeFly,
+            ..CameraComponent::new(60_f32.to_radians())
+        }
     ));
 
     world.insert(entity!(
@@ -75,21 +259,30 @@ fn main() {
         )),
         renderer.load(&DrawDescriptor {
             mesh: Mesh::Cube,
-            texture: Path::new("res/textures/container.jpg").into(),
+            texture: TextureDescriptor::new(Texture::File(
+                Path::new("res/textures/container.jpg").into()
+            )),
+            normal_map: None,
+            alpha_mode: AlphaMode::Opaque,
         })
     ));
 
-    world.insert(entity!(
-        TransformComponent::from_mat4(Mat4::from_scale_rotation_translation(
-            Vec3::new(0.05, 0.05, 0.05),
-            Quat::IDENTITY,
-            Vec3::new(1.0, -2.0, 7.0)
+    for draw in load_model(
+        renderer,
+        Mesh::Gltf(Path::new("res/gltf/teapot.gltf").into()),
+        TextureDescriptor::new(Texture::File(
+            Path::new("res/textures/container.jpg").into(),
         )),
-        renderer.load(&DrawDescriptor {
-            mesh: Mesh::Gltf(Path::new("res/gltf/teapot.gltf").into()),
-            texture: Path::new("res/textures/container.jpg").into(),
-        })
-    ));
+    ) {
+        world.insert(entity!(
+            TransformComponent::from_mat4(Mat4::from_scale_rotation_translation(
+                Vec3::new(0.05, 0.05, 0.05),
+                Quat::IDENTITY,
+                Vec3::new(1.0, -2.0, 7.0)
+            )),
+            draw
+        ));
+    }
 
     world.insert(entity!(
         TransformComponent::from_mat4(Mat4::from_rotation_translation(
@@ -103,57 +296,93 @@ fn main() {
         )),
         renderer.load(&DrawDescriptor {
             mesh: Mesh::Cube,
-            texture: Path::new("res/textures/container.jpg").into(),
+            texture: TextureDescriptor::new(Texture::File(
+                Path::new("res/textures/container.jpg").into()
+            )),
+            normal_map: None,
+            alpha_mode: AlphaMode::Opaque,
         })
     ));
 
-    let mut schedule = schedule!(
-        task::System(SpinCube),
-        task::System(CameraSystem::default()),
-        task::System(CloseSystem),
-    );
+    world
+}
 
-    let mut average_dt = SingleSumSMA::<_, _, 50>::from_zero(Duration::ZERO);
-    let mut next_frame_start_instant = Instant::now();
+fn print_state(world: &mut World<Registry, Resources>) {
+    for result!(transform) in world
+        .query(Query::<Views!(&TransformComponent), filter::None>::new())
+        .iter
+    {
+        println!(
+            "Transform: translation {} rotation {} scale {}",
+            transform.translation, transform.rotation, transform.scale
+        );
+    }
+}
 
-    event_loop.run(move |event, window_target| match event {
-        Event::DeviceEvent { event, .. } => {
-            world.get_mut::<InputResource, _>().device_event(&event);
-        }
-        Event::WindowEvent { event, .. } => {
-            world.get_mut::<InputResource, _>().window_event(&event);
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
 
-            match event {
-                WindowEvent::CloseRequested => window_target.exit(),
-                WindowEvent::Resized(size) => world
-                    .get_mut::<CameraResource, _>()
-                    .resize(size.width as f32 / size.height as f32),
-                _ => (),
-            }
-        }
-        Event::AboutToWait => {
-            if Instant::now() >= next_frame_start_instant {
-                let start = Instant::now();
+    use glam::Vec3A;
+    use winit::{event::ElementState, keyboard::KeyCode};
 
-                world.run_schedule(&mut schedule);
+    use super::*;
+    use crate::resources::input::InputEvent;
 
-                renderer.render(&mut world);
-                
-                if world.get::<ExitResource, _>().0 {
-                    window_target.exit();
-                }
+    fn get_transforms(
+        world: &mut World<Registry, Resources>,
+    ) -> HashMap<entity::Identifier, (Vec3A, Quat, Vec3A)> {
+        world
+            .query(Query::<Views!(entity::Identifier, &TransformComponent), filter::None>::new())
+            .iter
+            .map(|result!(entity, transform)| {
+                (entity, (transform.translation, transform.rotation, transform.scale))
+            })
+            .collect()
+    }
 
-                world.get_mut::<InputResource, _>().tick();
-                world.get_mut::<TimerResource, _>().tick();
+    #[test]
+    fn replay_matches_recording() {
+        let mut renderer = HeadlessRenderer::default();
+        let mut world = create_world(&mut renderer, PhysicalSize::new(800, 600), true);
+        let mut schedule = systems::schedule();
 
-                average_dt.add_sample(world.get::<TimerResource, _>().get_dt());
-                println!("{:.0} FPS", average_dt.get_average().as_secs_f32().recip());
+        world.get_mut::<InputResource, _>().start_recording();
 
-                let frametime = Instant::now() - start;
+        let dt = Duration::from_millis(16);
+        for frame in 0..120 {
+            let input = world.get_mut::<InputResource, _>();
 
-                next_frame_start_instant = Instant::now() + TARGET_FRAMETIME - frametime;
+            match frame {
+                0 => input.input_event(InputEvent::Key(KeyCode::KeyW, ElementState::Pressed)),
+                10 => input.input_event(InputEvent::Key(KeyCode::KeyW, ElementState::Released)),
+                _ => (),
             }
+            // Looks around and back, so something is under the cursor at the end
+            let motion = if frame < 60 { 2.0 } else { -2.0 };
+            input.input_event(InputEvent::MouseMotion(motion, motion * 0.5));
+            input.input_event(InputEvent::CursorMoved(400.0, 300.0 + frame as f32 * 0.1));
+
+            run_frame(&mut world, &mut schedule, &mut renderer);
+
+            world.get_mut::<TimerResource, _>().tick_with(dt);
+            world.get_mut::<InputResource, _>().tick(dt);
         }
-        _ => (),
-    }).unwrap();
+
+        let mut recording = world
+            .get_mut::<InputResource, _>()
+            .stop_recording()
+            .unwrap();
+        let path = std::env::temp_dir().join("replay_matches_recording.bin");
+        recording.save(&path).unwrap();
+
+        let mut replayed = replay_recording(InputRecording::load(&path).unwrap());
+
+        assert!(world.get::<PickingResource, _>().hovered.is_some());
+        assert_eq!(get_transforms(&mut world), get_transforms(&mut replayed));
+        assert_eq!(
+            world.get::<PickingResource, _>().hovered.map(|hit| hit.point),
+            replayed.get::<PickingResource, _>().hovered.map(|hit| hit.point),
+        );
+    }
 }
@@ -0,0 +1,95 @@
+use crate::geometry::{Aabb, Ray};
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct BvhNode {
+    pub bounds: Aabb,
+    // Leaves have no children and hold one item
+    pub children: Option<(usize, usize)>,
+    pub item: usize,
+}
+
+// Bounding volume hierarchy over the bounds of items, rebuilt whenever they move
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct Bvh {
+    // The root is the first node
+    pub nodes: Vec<BvhNode>,
+}
+
+impl Bvh {
+    pub fn new(mut items: Vec<(usize, Aabb)>) -> Self {
+        let mut bvh = Self::default();
+
+        if !items.is_empty() {
+            bvh.build(&mut items);
+        }
+
+        bvh
+    }
+
+    // Splits in the middle of the longest axis of the centers
+    fn build(&mut self, items: &mut [(usize, Aabb)]) -> usize {
+        let index = self.nodes.len();
+        let bounds = items
+            .iter()
+            .fold(Aabb::EMPTY, |bounds, (_, item)| bounds.union(item));
+
+        self.nodes.push(BvhNode {
+            bounds,
+            children: None,
+            item: items[0].0,
+        });
+
+        if items.len() > 1 {
+            let centers = Aabb::from_points(items.iter().map(|(_, item)| item.center()));
+            let extents = centers.max - centers.min;
+            let axis = if extents.x >= extents.y && extents.x >= extents.z {
+                0
+            } else if extents.y >= extents.z {
+                1
+            } else {
+                2
+            };
+
+            items.sort_by(|a, b| a.1.center()[axis].total_cmp(&b.1.center()[axis]));
+            let (left, right) = items.split_at_mut(items.len() / 2);
+
+            let children = (self.build(left), self.build(right));
+            self.nodes[index].children = Some(children);
+        }
+
+        index
+    }
+
+    fn visit(&self, overlaps: impl Fn(&Aabb) -> bool) -> Vec<usize> {
+        let mut items = Vec::new();
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !overlaps(&node.bounds) {
+                continue;
+            }
+
+            match node.children {
+                Some((left, right)) => stack.extend([left, right]),
+                None => items.push(node.item),
+            }
+        }
+
+        items
+    }
+
+    // Items whose bounds overlap these
+    pub fn query(&self, bounds: &Aabb) -> Vec<usize> {
+        self.visit(|node| node.intersects(bounds))
+    }
+
+    // Items whose bounds the ray hits
+    pub fn query_ray(&self, ray: &Ray, max_distance: f32) -> Vec<usize> {
+        self.visit(|node| node.ray_intersection(ray, max_distance).is_some())
+    }
+}
@@ -0,0 +1,565 @@
+use glam::{Mat4, Vec3A};
+
+use crate::geometry::{Aabb, TriangleMesh};
+
+use super::{
+    hull::{ConvexHull, HullFace},
+    shape::WorldShape,
+};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Contact {
+    // Halfway between the two surfaces
+    pub point: Vec3A,
+    // From the first shape towards the second
+    pub normal: Vec3A,
+    pub depth: f32,
+}
+
+impl Contact {
+    fn flip(self) -> Self {
+        Self {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+// Contacts where the shapes overlap, none for two meshes
+pub fn collide(a: &WorldShape, b: &WorldShape) -> Vec<Contact> {
+    match (a, b) {
+        (WorldShape::Sphere { center, radius }, _) => core_contacts(&[*center], *radius, b),
+        (
+            WorldShape::Capsule {
+                a: start,
+                b: end,
+                radius,
+            },
+            _,
+        ) => core_contacts(&[*start, *end], *radius, b),
+        (WorldShape::Polyhedron(hull), WorldShape::Polyhedron(other)) => hull_contacts(hull, other),
+        (WorldShape::Polyhedron(hull), WorldShape::Mesh { mesh, mat }) => {
+            mesh_contacts(mesh, mat, &hull.get_bounds(), |triangle| {
+                hull_contacts(hull, triangle)
+            })
+        }
+        (WorldShape::Mesh { .. }, WorldShape::Mesh { .. }) => Vec::new(),
+        _ => collide(b, a).into_iter().map(Contact::flip).collect(),
+    }
+}
+
+// Spheres and capsules are a point or a segment grown by the radius
+fn core_contacts(core: &[Vec3A], radius: f32, other: &WorldShape) -> Vec<Contact> {
+    let segment_contact = |other_core: &[Vec3A], other_radius: f32| {
+        let (point, other_point) = match (core, other_core) {
+            ([point], [other_point]) => (*point, *other_point),
+            ([point], [a, b]) => (*point, closest_point_on_segment(*a, *b, *point)),
+            ([a, b], [point]) => (closest_point_on_segment(*a, *b, *point), *point),
+            ([a, b], [c, d]) => closest_points_on_segments(*a, *b, *c, *d),
+            _ => unreachable!(),
+        };
+
+        sphere_contact(point, radius, other_point, other_radius)
+            .into_iter()
+            .collect()
+    };
+
+    match other {
+        WorldShape::Sphere { center, radius } => segment_contact(&[*center], *radius),
+        WorldShape::Capsule { a, b, radius } => segment_contact(&[*a, *b], *radius),
+        WorldShape::Polyhedron(hull) => core_hull_contacts(core, radius, hull),
+        WorldShape::Mesh { mesh, mat } => {
+            let bounds = Aabb::from_points(core.iter().copied());
+            let bounds = Aabb::new(
+                bounds.min - Vec3A::splat(radius),
+                bounds.max + Vec3A::splat(radius),
+            );
+
+            mesh_contacts(mesh, mat, &bounds, |triangle| {
+                core_hull_contacts(core, radius, triangle)
+            })
+        }
+    }
+}
+
+fn sphere_contact(
+    center: Vec3A,
+    radius: f32,
+    other_center: Vec3A,
+    other_radius: f32,
+) -> Option<Contact> {
+    let offset = other_center - center;
+    let distance = offset.length();
+    let depth = radius + other_radius - distance;
+
+    (depth > 0.0).then(|| {
+        // Straight up when the centers are on top of each other
+        let normal = if distance > 1e-6 {
+            offset / distance
+        } else {
+            Vec3A::Y
+        };
+
+        Contact {
+            point: center + normal * (radius - depth * 0.5),
+            normal,
+            depth,
+        }
+    })
+}
+
+fn mesh_contacts(
+    mesh: &TriangleMesh,
+    mat: &Mat4,
+    bounds: &Aabb,
+    contacts: impl Fn(&ConvexHull) -> Vec<Contact>,
+) -> Vec<Contact> {
+    let local_bounds = bounds.transform(&mat.inverse());
+
+    if !local_bounds.intersects(&mesh.bounds) {
+        return Vec::new();
+    }
+
+    mesh.triangles()
+        .filter(|triangle| local_bounds.intersects(&Aabb::from_points(*triangle)))
+        .flat_map(|triangle| {
+            let [a, b, c] = triangle.map(|point| mat.transform_point3a(point));
+            contacts(&ConvexHull::triangle(a, b, c))
+        })
+        .collect()
+}
+
+// The normal points from the core to the hull
+fn core_hull_contacts(core: &[Vec3A], radius: f32, hull: &ConvexHull) -> Vec<Contact> {
+    let contact = |point: Vec3A, closest: Vec3A| {
+        let offset = closest - point;
+        let distance = offset.length();
+
+        (distance > 1e-6 && distance < radius).then(|| {
+            let normal = offset / distance;
+            Contact {
+                point: (closest + point + normal * radius) * 0.5,
+                normal,
+                depth: radius - distance,
+            }
+        })
+    };
+
+    // Each end of a capsule gets its own contact so it can lie flat
+    let closest: Option<Vec<_>> = core
+        .iter()
+        .map(|point| closest_point_on_hull(hull, *point).map(|closest| (*point, closest)))
+        .collect();
+
+    if let Some(closest) = closest {
+        let contacts: Vec<_> = closest
+            .into_iter()
+            .filter_map(|(point, closest)| contact(point, closest))
+            .collect();
+
+        if !contacts.is_empty() || core.len() == 1 {
+            return contacts;
+        }
+
+        if let Some((point, closest)) = closest_points_on_segment_and_hull(core[0], core[1], hull) {
+            return contact(point, closest).into_iter().collect();
+        }
+    }
+
+    // The core is inside, so it's pushed out through the face that needs the least
+    let face_depth = |face: &HullFace| {
+        let plane = face.normal.dot(hull.vertices[face.indices[0]]);
+        let distances: Vec<_> = core
+            .iter()
+            .map(|point| face.normal.dot(*point) - plane)
+            .collect();
+        let depth = radius - distances.iter().copied().fold(f32::INFINITY, f32::min);
+
+        (depth, distances)
+    };
+
+    let Some((face, (_, distances))) = hull
+        .faces
+        .iter()
+        .map(|face| (face, face_depth(face)))
+        .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+    else {
+        return Vec::new();
+    };
+
+    core.iter()
+        .zip(distances)
+        .filter(|(_, distance)| *distance < radius)
+        .map(|(point, distance)| Contact {
+            point: *point - face.normal * (distance + radius) * 0.5,
+            normal: -face.normal,
+            depth: radius - distance,
+        })
+        .collect()
+}
+
+// Separating axis test between two hulls. Faces are clipped against each other for a
+// contact per corner, crossing edges only touch at one point.
+fn hull_contacts(a: &ConvexHull, b: &ConvexHull) -> Vec<Contact> {
+    let face_axis = |hull: &ConvexHull, other: &ConvexHull| {
+        let mut best: Option<(f32, usize)> = None;
+
+        for (i, face) in hull.faces.iter().enumerate() {
+            let plane = face.normal.dot(hull.vertices[face.indices[0]]);
+            let depth = plane - other.get_interval(face.normal).0;
+
+            if depth < 0.0 {
+                return None;
+            }
+            if best.map_or(true, |(best, _)| depth < best) {
+                best = Some((depth, i));
+            }
+        }
+
+        best
+    };
+
+    let Some((depth_a, face_a)) = face_axis(a, b) else {
+        return Vec::new();
+    };
+    let Some((depth_b, face_b)) = face_axis(b, a) else {
+        return Vec::new();
+    };
+
+    let directions = |hull: &ConvexHull| {
+        let mut directions: Vec<Vec3A> = Vec::new();
+
+        for (start, end) in &hull.edges {
+            let direction = (hull.vertices[*end] - hull.vertices[*start]).normalize_or_zero();
+            if !directions
+                .iter()
+                .any(|other| other.dot(direction).abs() > 0.999)
+            {
+                directions.push(direction);
+            }
+        }
+
+        directions
+    };
+
+    let to_b = b.get_center() - a.get_center();
+    let mut best_edge: Option<(f32, Vec3A, Vec3A, Vec3A)> = None;
+
+    for direction_a in directions(a) {
+        for direction_b in directions(b) {
+            let axis = direction_a.cross(direction_b);
+            if axis.length_squared() < 1e-6 {
+                continue;
+            }
+
+            let axis = axis.normalize();
+            let axis = if axis.dot(to_b) < 0.0 { -axis } else { axis };
+            let (min_a, max_a) = a.get_interval(axis);
+            let (min_b, max_b) = b.get_interval(axis);
+            let depth = max_a - min_b;
+
+            if depth < 0.0 || max_b < min_a {
+                return Vec::new();
+            }
+            if best_edge.map_or(true, |(best, ..)| depth < best) {
+                best_edge = Some((depth, axis, direction_a, direction_b));
+            }
+        }
+    }
+
+    // Faces give more contacts, so edges have to be clearly better
+    let face_depth = depth_a.min(depth_b);
+
+    match best_edge {
+        Some((depth, axis, direction_a, direction_b)) if depth < face_depth * 0.95 - 0.001 => {
+            let support_edge = |hull: &ConvexHull, direction: Vec3A, sign: f32| {
+                hull.edges
+                    .iter()
+                    .map(|(start, end)| (hull.vertices[*start], hull.vertices[*end]))
+                    .filter(|(start, end)| {
+                        (*end - *start).normalize_or_zero().dot(direction).abs() > 0.999
+                    })
+                    .max_by(|x, y| {
+                        let extent = |(start, end): &(Vec3A, Vec3A)| sign * axis.dot(*start + *end);
+                        extent(x).total_cmp(&extent(y))
+                    })
+                    .unwrap()
+            };
+
+            let (start_a, end_a) = support_edge(a, direction_a, 1.0);
+            let (start_b, end_b) = support_edge(b, direction_b, -1.0);
+            let (point_a, point_b) = closest_points_on_segments(start_a, end_a, start_b, end_b);
+
+            vec![Contact {
+                point: (point_a + point_b) * 0.5,
+                normal: axis,
+                depth,
+            }]
+        }
+        _ if depth_a <= depth_b => face_contacts(a, &a.faces[face_a], b),
+        _ => face_contacts(b, &b.faces[face_b], a)
+            .into_iter()
+            .map(Contact::flip)
+            .collect(),
+    }
+}
+
+// The face of the incident hull that faces the reference face most is clipped by the sides
+// of the reference face, what is left below it are the contacts
+fn face_contacts(reference: &ConvexHull, face: &HullFace, incident: &ConvexHull) -> Vec<Contact> {
+    let Some(incident_face) = incident.faces.iter().min_by(|a, b| {
+        face.normal
+            .dot(a.normal)
+            .total_cmp(&face.normal.dot(b.normal))
+    }) else {
+        return Vec::new();
+    };
+
+    let reference_vertices = reference.get_face_vertices(face);
+    let mut polygon = incident.get_face_vertices(incident_face);
+
+    for (i, start) in reference_vertices.iter().enumerate() {
+        let end = reference_vertices[(i + 1) % reference_vertices.len()];
+        let side = (end - *start).cross(face.normal).normalize_or_zero();
+        polygon = clip_polygon(&polygon, side, side.dot(*start));
+    }
+
+    let plane = face.normal.dot(reference_vertices[0]);
+
+    polygon
+        .into_iter()
+        .filter_map(|point| {
+            let distance = face.normal.dot(point) - plane;
+
+            (distance < 0.0).then(|| Contact {
+                point: point - face.normal * distance * 0.5,
+                normal: face.normal,
+                depth: -distance,
+            })
+        })
+        .collect()
+}
+
+// Keeps the part behind the plane
+fn clip_polygon(polygon: &[Vec3A], normal: Vec3A, plane: f32) -> Vec<Vec3A> {
+    let mut clipped = Vec::new();
+
+    for (i, start) in polygon.iter().enumerate() {
+        let end = polygon[(i + 1) % polygon.len()];
+        let (start_distance, end_distance) = (normal.dot(*start) - plane, normal.dot(end) - plane);
+
+        if start_distance <= 0.0 {
+            clipped.push(*start);
+        }
+        if (start_distance <= 0.0) != (end_distance <= 0.0) {
+            let t = start_distance / (start_distance - end_distance);
+            clipped.push(start.lerp(end, t));
+        }
+    }
+
+    clipped
+}
+
+pub fn closest_point_on_segment(a: Vec3A, b: Vec3A, point: Vec3A) -> Vec3A {
+    let edge = b - a;
+    let length_squared = edge.length_squared();
+
+    if length_squared < 1e-12 {
+        return a;
+    }
+
+    a + edge * ((point - a).dot(edge) / length_squared).clamp(0.0, 1.0)
+}
+
+pub fn closest_points_on_segments(a: Vec3A, b: Vec3A, c: Vec3A, d: Vec3A) -> (Vec3A, Vec3A) {
+    let (first, second, offset) = (b - a, d - c, a - c);
+    let (length_first, length_second) = (first.length_squared(), second.length_squared());
+    let (along_first, along_second) = (first.dot(offset), second.dot(offset));
+
+    if length_first < 1e-12 {
+        return (a, closest_point_on_segment(c, d, a));
+    }
+    if length_second < 1e-12 {
+        return (closest_point_on_segment(a, b, c), c);
+    }
+
+    let cross = first.dot(second);
+    let denominator = length_first * length_second - cross * cross;
+    // Parallel segments take any point, the start of the first one
+    let mut s = if denominator > 1e-12 {
+        ((cross * along_second - along_first * length_second) / denominator).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let mut t = (cross * s + along_second) / length_second;
+
+    if t < 0.0 {
+        t = 0.0;
+        s = (-along_first / length_first).clamp(0.0, 1.0);
+    } else if t > 1.0 {
+        t = 1.0;
+        s = ((cross - along_first) / length_first).clamp(0.0, 1.0);
+    }
+
+    (a + first * s, c + second * t)
+}
+
+// The polygon is counter-clockwise around the normal
+pub fn closest_point_on_polygon(polygon: &[Vec3A], normal: Vec3A, point: Vec3A) -> Vec3A {
+    let projected = point - normal * normal.dot(point - polygon[0]);
+    let edges = || {
+        polygon
+            .iter()
+            .enumerate()
+            .map(|(i, start)| (*start, polygon[(i + 1) % polygon.len()]))
+    };
+
+    if edges().all(|(start, end)| (end - start).cross(projected - start).dot(normal) >= 0.0) {
+        return projected;
+    }
+
+    edges()
+        .map(|(start, end)| closest_point_on_segment(start, end, point))
+        .min_by(|a, b| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+        .unwrap()
+}
+
+// None when the point is inside
+pub fn closest_point_on_hull(hull: &ConvexHull, point: Vec3A) -> Option<Vec3A> {
+    hull.faces
+        .iter()
+        .filter(|face| face.normal.dot(point - hull.vertices[face.indices[0]]) > 0.0)
+        .map(|face| closest_point_on_polygon(&hull.get_face_vertices(face), face.normal, point))
+        .min_by(|a, b| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+}
+
+// The closest points on the segment and the hull, None when they intersect
+pub fn closest_points_on_segment_and_hull(
+    a: Vec3A,
+    b: Vec3A,
+    hull: &ConvexHull,
+) -> Option<(Vec3A, Vec3A)> {
+    let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+
+    for face in &hull.faces {
+        let distance = face.normal.dot(a - hull.vertices[face.indices[0]]);
+        let speed = face.normal.dot(b - a);
+
+        if speed.abs() < 1e-12 {
+            if distance > 0.0 {
+                exit = -1.0;
+            }
+        } else if speed < 0.0 {
+            enter = enter.max(-distance / speed);
+        } else {
+            exit = exit.min(-distance / speed);
+        }
+    }
+
+    if enter <= exit {
+        return None;
+    }
+
+    hull.faces
+        .iter()
+        .flat_map(|face| {
+            let polygon = hull.get_face_vertices(face);
+            let ends =
+                [a, b].map(|end| (end, closest_point_on_polygon(&polygon, face.normal, end)));
+            let edges: Vec<_> = (0..polygon.len())
+                .map(|i| {
+                    closest_points_on_segments(a, b, polygon[i], polygon[(i + 1) % polygon.len()])
+                })
+                .collect();
+
+            ends.into_iter().chain(edges)
+        })
+        .min_by(|x, y| {
+            x.0.distance_squared(x.1)
+                .total_cmp(&y.0.distance_squared(y.1))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cuboid(center: Vec3A, half_extents: Vec3A) -> WorldShape {
+        WorldShape::Polyhedron(
+            ConvexHull::cuboid(half_extents).transform(&Mat4::from_translation(center.into())),
+        )
+    }
+
+    fn assert_contacts(contacts: &[Contact], count: usize, normal: Vec3A, depth: f32) {
+        assert_eq!(contacts.len(), count);
+        for contact in contacts {
+            assert!(contact.normal.abs_diff_eq(normal, 1e-4));
+            assert!((contact.depth - depth).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn sphere_box() {
+        let cube = cuboid(Vec3A::ZERO, Vec3A::ONE);
+        let sphere = WorldShape::Sphere {
+            center: Vec3A::new(0.2, 1.4, -0.3),
+            radius: 0.5,
+        };
+
+        assert_contacts(&collide(&cube, &sphere), 1, Vec3A::Y, 0.1);
+        assert_contacts(&collide(&sphere, &cube), 1, Vec3A::NEG_Y, 0.1);
+    }
+
+    #[test]
+    fn box_box() {
+        let below = cuboid(Vec3A::ZERO, Vec3A::ONE);
+        let above = cuboid(Vec3A::new(0.5, 1.4, 0.0), Vec3A::splat(0.5));
+
+        let contacts = collide(&below, &above);
+        assert_contacts(&contacts, 4, Vec3A::Y, 0.1);
+        for contact in contacts {
+            assert!((contact.point.y - 0.95).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn capsule_hull() {
+        let ground = cuboid(Vec3A::ZERO, Vec3A::new(2.0, 1.0, 2.0));
+        let capsule = WorldShape::Capsule {
+            a: Vec3A::new(-1.0, 1.3, 0.0),
+            b: Vec3A::new(1.0, 1.3, 0.0),
+            radius: 0.5,
+        };
+
+        // Lying down, both ends touch
+        assert_contacts(&collide(&ground, &capsule), 2, Vec3A::Y, 0.2);
+
+        let standing = WorldShape::Capsule {
+            a: Vec3A::new(0.0, 1.4, 0.0),
+            b: Vec3A::new(0.0, 3.0, 0.0),
+            radius: 0.5,
+        };
+        assert_contacts(&collide(&standing, &ground), 1, Vec3A::NEG_Y, 0.1);
+    }
+
+    #[test]
+    fn separated() {
+        let cube = cuboid(Vec3A::ZERO, Vec3A::ONE);
+
+        assert!(collide(&cube, &cuboid(Vec3A::new(2.1, 0.0, 0.0), Vec3A::ONE)).is_empty());
+        assert!(collide(
+            &cube,
+            &WorldShape::Sphere {
+                center: Vec3A::splat(1.5),
+                radius: 0.8,
+            }
+        )
+        .is_empty());
+    }
+}
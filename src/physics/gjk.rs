@@ -0,0 +1,191 @@
+use glam::Vec3A;
+
+const MAX_ITERATIONS: usize = 32;
+
+// A point of the Minkowski difference, with the points of each shape it came from
+#[derive(Clone, Copy)]
+struct SupportPoint {
+    point: Vec3A,
+    a: Vec3A,
+    b: Vec3A,
+}
+
+fn support(points: &[Vec3A], direction: Vec3A) -> Vec3A {
+    *points
+        .iter()
+        .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+        .unwrap()
+}
+
+// The closest points of the convex hulls of two point sets, None when they overlap
+pub fn closest_points(a: &[Vec3A], b: &[Vec3A]) -> Option<(Vec3A, Vec3A)> {
+    let support_point = |direction: Vec3A| {
+        let (a, b) = (support(a, direction), support(b, -direction));
+        SupportPoint { point: a - b, a, b }
+    };
+
+    let mut simplex = vec![support_point(b[0] - a[0])];
+    let mut closest_points = (simplex[0].a, simplex[0].b);
+
+    for _ in 0..MAX_ITERATIONS {
+        let weights = closest_on_simplex(&mut simplex)?;
+        let mix = |get: fn(&SupportPoint) -> Vec3A| {
+            simplex
+                .iter()
+                .zip(&weights)
+                .map(|(point, weight)| get(point) * *weight)
+                .sum::<Vec3A>()
+        };
+
+        let closest = mix(|point| point.point);
+        let length_squared = closest.length_squared();
+        if length_squared < 1e-12 {
+            return None;
+        }
+        closest_points = (mix(|point| point.a), mix(|point| point.b));
+
+        // Done when nothing is noticeably closer to the origin
+        let next = support_point(-closest);
+        if length_squared - closest.dot(next.point) <= 1e-6 * length_squared {
+            break;
+        }
+        simplex.push(next);
+    }
+
+    Some(closest_points)
+}
+
+// Reduces the simplex to the smallest part that has the point closest to the origin,
+// returning the weights of that point. None when the origin is inside.
+fn closest_on_simplex(simplex: &mut Vec<SupportPoint>) -> Option<Vec<f32>> {
+    let points: Vec<Vec3A> = simplex.iter().map(|point| point.point).collect();
+
+    let (indices, weights) = match points[..] {
+        [_] => (vec![0], vec![1.0]),
+        [a, b] => closest_on_segment(a, b),
+        [a, b, c] => closest_on_triangle(a, b, c),
+        [a, b, c, d] => closest_on_tetrahedron([a, b, c, d])?,
+        _ => unreachable!(),
+    };
+
+    *simplex = indices.into_iter().map(|i| simplex[i]).collect();
+
+    Some(weights)
+}
+
+fn closest_on_segment(a: Vec3A, b: Vec3A) -> (Vec<usize>, Vec<f32>) {
+    let ab = b - a;
+    let t = -a.dot(ab) / ab.length_squared();
+
+    // Also catches a segment of length zero
+    if t.is_nan() || t <= 0.0 {
+        (vec![0], vec![1.0])
+    } else if t >= 1.0 {
+        (vec![1], vec![1.0])
+    } else {
+        (vec![0, 1], vec![1.0 - t, t])
+    }
+}
+
+// From Real-Time Collision Detection, with the origin as the point
+fn closest_on_triangle(a: Vec3A, b: Vec3A, c: Vec3A) -> (Vec<usize>, Vec<f32>) {
+    let ab = b - a;
+    let ac = c - a;
+
+    let d1 = ab.dot(-a);
+    let d2 = ac.dot(-a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (vec![0], vec![1.0]);
+    }
+
+    let d3 = ab.dot(-b);
+    let d4 = ac.dot(-b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return (vec![1], vec![1.0]);
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let t = d1 / (d1 - d3);
+        return (vec![0, 1], vec![1.0 - t, t]);
+    }
+
+    let d5 = ab.dot(-c);
+    let d6 = ac.dot(-c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return (vec![2], vec![1.0]);
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let t = d2 / (d2 - d6);
+        return (vec![0, 2], vec![1.0 - t, t]);
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (vec![1, 2], vec![1.0 - t, t]);
+    }
+
+    let sum = va + vb + vc;
+    if sum <= f32::EPSILON * ab.cross(ac).length().max(f32::MIN_POSITIVE) {
+        // Flat, so the closest point is on one of the edges
+        return [[0, 1], [0, 2], [1, 2]]
+            .into_iter()
+            .map(|edge| {
+                let points = [a, b, c];
+                let (indices, weights) = closest_on_segment(points[edge[0]], points[edge[1]]);
+                let indices: Vec<usize> = indices.into_iter().map(|i| edge[i]).collect();
+                let closest: Vec3A = indices
+                    .iter()
+                    .zip(&weights)
+                    .map(|(i, weight)| points[*i] * *weight)
+                    .sum();
+
+                (closest.length_squared(), indices, weights)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, indices, weights)| (indices, weights))
+            .unwrap();
+    }
+
+    let v = vb / sum;
+    let w = vc / sum;
+    (vec![0, 1, 2], vec![1.0 - v - w, v, w])
+}
+
+fn closest_on_tetrahedron(points: [Vec3A; 4]) -> Option<(Vec<usize>, Vec<f32>)> {
+    let mut closest: Option<(f32, Vec<usize>, Vec<f32>)> = None;
+
+    for face in [[0, 1, 2, 3], [0, 1, 3, 2], [0, 2, 3, 1], [1, 2, 3, 0]] {
+        let [a, b, c, other] = face.map(|i| points[i]);
+        let normal = (b - a).cross(c - a);
+        let origin_side = normal.dot(-a);
+        let other_side = normal.dot(other - a);
+
+        // Only faces the origin is in front of, but all of them when the tetrahedron is flat
+        let flat = other_side.abs() <= 1e-6 * normal.length() * (other - a).length();
+        if origin_side * other_side >= 0.0 && !flat {
+            continue;
+        }
+
+        let (indices, weights) = closest_on_triangle(a, b, c);
+        let indices: Vec<usize> = indices.into_iter().map(|i| face[i]).collect();
+        let distance = indices
+            .iter()
+            .zip(&weights)
+            .map(|(i, weight)| points[*i] * *weight)
+            .sum::<Vec3A>()
+            .length_squared();
+
+        if closest
+            .as_ref()
+            .map_or(true, |closest| distance < closest.0)
+        {
+            closest = Some((distance, indices, weights));
+        }
+    }
+
+    closest.map(|(_, indices, weights)| (indices, weights))
+}
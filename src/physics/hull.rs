@@ -0,0 +1,298 @@
+use glam::{Mat3A, Mat4, Vec3A};
+
+use crate::geometry::Aabb;
+
+// A convex polygon, counter-clockwise around its outward normal
+#[derive(PartialEq, Clone, Debug)]
+pub struct HullFace {
+    pub normal: Vec3A,
+    pub indices: Vec<usize>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct ConvexHull {
+    pub vertices: Vec<Vec3A>,
+    pub faces: Vec<HullFace>,
+    // Each edge once, as vertex indices
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl ConvexHull {
+    fn from_faces(vertices: Vec<Vec3A>, faces: Vec<HullFace>) -> Self {
+        let mut edges = Vec::new();
+
+        for face in &faces {
+            for (i, a) in face.indices.iter().enumerate() {
+                let b = face.indices[(i + 1) % face.indices.len()];
+                let edge = (*a.min(&b), *a.max(&b));
+
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        Self {
+            vertices,
+            faces,
+            edges,
+        }
+    }
+
+    pub fn cuboid(half_extents: Vec3A) -> Self {
+        let vertices = (0..8)
+            .map(|i| {
+                let sign = |bit: u32| if i & bit == 0 { -1.0 } else { 1.0 };
+                Vec3A::new(sign(1), sign(2), sign(4)) * half_extents
+            })
+            .collect();
+
+        let face = |normal: Vec3A, indices: [usize; 4]| HullFace {
+            normal,
+            indices: indices.to_vec(),
+        };
+        let faces = vec![
+            face(Vec3A::NEG_X, [0, 4, 6, 2]),
+            face(Vec3A::X, [1, 3, 7, 5]),
+            face(Vec3A::NEG_Y, [0, 1, 5, 4]),
+            face(Vec3A::Y, [2, 6, 7, 3]),
+            face(Vec3A::NEG_Z, [0, 2, 3, 1]),
+            face(Vec3A::Z, [4, 5, 7, 6]),
+        ];
+
+        Self::from_faces(vertices, faces)
+    }
+
+    // A flat hull with a face on either side, for triangles of meshes
+    pub fn triangle(a: Vec3A, b: Vec3A, c: Vec3A) -> Self {
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        let faces = vec![
+            HullFace {
+                normal,
+                indices: vec![0, 1, 2],
+            },
+            HullFace {
+                normal: -normal,
+                indices: vec![0, 2, 1],
+            },
+        ];
+
+        Self::from_faces(vec![a, b, c], faces)
+    }
+
+    // Incremental hull of the points, None when they are all on a plane. Triangles on the
+    // same plane are merged into one face.
+    pub fn new(points: &[Vec3A]) -> Option<Self> {
+        let bounds = Aabb::from_points(points.iter().copied());
+        let epsilon = (bounds.max - bounds.min).max_element() * 1e-5;
+
+        let p0 = *points.first()?;
+        let p1 = *points
+            .iter()
+            .max_by(|a, b| a.distance_squared(p0).total_cmp(&b.distance_squared(p0)))?;
+        let line_distance = |p: &Vec3A| (*p - p0).cross(p1 - p0).length_squared();
+        let p2 = *points
+            .iter()
+            .max_by(|a, b| line_distance(a).total_cmp(&line_distance(b)))?;
+        let plane_normal = (p1 - p0).cross(p2 - p0).normalize_or_zero();
+        let p3 = *points.iter().max_by(|a, b| {
+            let distance = |p: &Vec3A| plane_normal.dot(*p - p0).abs();
+            distance(a).total_cmp(&distance(b))
+        })?;
+
+        if plane_normal.dot(p3 - p0).abs() <= epsilon {
+            return None;
+        }
+
+        let mut vertices = vec![p0, p1, p2, p3];
+        let interior = (p0 + p1 + p2 + p3) * 0.25;
+        let plane = |vertices: &[Vec3A], [a, b, c]: [usize; 3]| {
+            let normal = (vertices[b] - vertices[a])
+                .cross(vertices[c] - vertices[a])
+                .normalize_or_zero();
+            (normal, normal.dot(vertices[a]))
+        };
+
+        let mut triangles: Vec<[usize; 3]> = [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]]
+            .into_iter()
+            .map(|[a, b, c]| {
+                let (normal, offset) = plane(&vertices, [a, b, c]);
+                if normal.dot(interior) > offset {
+                    [a, c, b]
+                } else {
+                    [a, b, c]
+                }
+            })
+            .collect();
+
+        for point in points {
+            let visible: Vec<bool> = triangles
+                .iter()
+                .map(|triangle| {
+                    let (normal, offset) = plane(&vertices, *triangle);
+                    normal.dot(*point) - offset > epsilon
+                })
+                .collect();
+
+            if !visible.contains(&true) {
+                continue;
+            }
+
+            let visible_edges: Vec<(usize, usize)> = triangles
+                .iter()
+                .zip(&visible)
+                .filter(|(_, visible)| **visible)
+                .flat_map(|(&[a, b, c], _)| [(a, b), (b, c), (c, a)])
+                .collect();
+            // Edges between a visible and a hidden triangle, where the new triangles go
+            let horizon: Vec<_> = visible_edges
+                .iter()
+                .filter(|(a, b)| !visible_edges.contains(&(*b, *a)))
+                .copied()
+                .collect();
+
+            let index = vertices.len();
+            vertices.push(*point);
+
+            let mut visible = visible.into_iter();
+            triangles.retain(|_| !visible.next().unwrap());
+            triangles.extend(horizon.into_iter().map(|(a, b)| [a, b, index]));
+        }
+
+        // Only keep the vertices that ended up on the hull
+        let mut remap = vec![None; vertices.len()];
+        let mut used = Vec::new();
+        for index in triangles.iter().flatten() {
+            if remap[*index].is_none() {
+                remap[*index] = Some(used.len());
+                used.push(vertices[*index]);
+            }
+        }
+        let triangles: Vec<_> = triangles
+            .iter()
+            .map(|triangle| triangle.map(|i| remap[i].unwrap()))
+            .collect();
+
+        let mut faces: Vec<HullFace> = Vec::new();
+        let mut merged = vec![false; triangles.len()];
+
+        for (i, triangle) in triangles.iter().enumerate() {
+            if merged[i] {
+                continue;
+            }
+
+            let (normal, offset) = plane(&used, *triangle);
+            let mut indices: Vec<usize> = Vec::new();
+
+            for (j, other) in triangles.iter().enumerate().skip(i) {
+                let (other_normal, other_offset) = plane(&used, *other);
+
+                if !merged[j]
+                    && normal.dot(other_normal) > 1.0 - 1e-4
+                    && (offset - other_offset).abs() <= epsilon * 10.0
+                {
+                    merged[j] = true;
+                    for index in other {
+                        if !indices.contains(index) {
+                            indices.push(*index);
+                        }
+                    }
+                }
+            }
+
+            // Sorted by angle around the middle, which works since the face is convex
+            let center = indices.iter().map(|i| used[*i]).sum::<Vec3A>() / indices.len() as f32;
+            let u = (used[indices[0]] - center).normalize_or_zero();
+            let v = normal.cross(u);
+            let angle = |i: &usize| {
+                let offset = used[*i] - center;
+                offset.dot(v).atan2(offset.dot(u))
+            };
+            indices.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+
+            faces.push(HullFace { normal, indices });
+        }
+
+        Some(Self::from_faces(used, faces))
+    }
+
+    pub fn transform(&self, mat: &Mat4) -> Self {
+        let normal_mat = Mat3A::from_mat4(*mat).inverse().transpose();
+
+        Self {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|vertex| mat.transform_point3a(*vertex))
+                .collect(),
+            faces: self
+                .faces
+                .iter()
+                .map(|face| HullFace {
+                    normal: (normal_mat * face.normal).normalize_or_zero(),
+                    indices: face.indices.clone(),
+                })
+                .collect(),
+            edges: self.edges.clone(),
+        }
+    }
+
+    pub fn get_bounds(&self) -> Aabb {
+        Aabb::from_points(self.vertices.iter().copied())
+    }
+
+    pub fn get_center(&self) -> Vec3A {
+        self.vertices.iter().sum::<Vec3A>() / self.vertices.len() as f32
+    }
+
+    pub fn get_face_vertices(&self, face: &HullFace) -> Vec<Vec3A> {
+        face.indices.iter().map(|i| self.vertices[*i]).collect()
+    }
+
+    // Projected onto the axis
+    pub fn get_interval(&self, axis: Vec3A) -> (f32, f32) {
+        self.vertices
+            .iter()
+            .map(|vertex| axis.dot(*vertex))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_point_cloud() {
+        // Face centers and the middle are inside or on the faces, so they don't add anything
+        let mut points: Vec<Vec3A> = (0..8)
+            .map(|i| {
+                let sign = |bit: u32| if i & bit == 0 { -1.0 } else { 1.0 };
+                Vec3A::new(sign(1), sign(2), sign(4))
+            })
+            .collect();
+        points.extend([
+            Vec3A::X,
+            Vec3A::NEG_X,
+            Vec3A::Y,
+            Vec3A::NEG_Y,
+            Vec3A::Z,
+            Vec3A::NEG_Z,
+            Vec3A::ZERO,
+        ]);
+
+        let hull = ConvexHull::new(&points).unwrap();
+
+        assert_eq!(hull.faces.len(), 6);
+        assert_eq!(hull.edges.len(), 12);
+        for face in &hull.faces {
+            assert_eq!(face.indices.len(), 4);
+            assert!(face.normal.abs().max_element() > 0.9999);
+            for index in &face.indices {
+                assert!((face.normal.dot(hull.vertices[*index]) - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod bvh;
+pub mod collision;
+pub mod gjk;
+pub mod hull;
+pub mod query;
+pub mod shape;
+pub mod solver;
@@ -0,0 +1,486 @@
+use brood::entity;
+use glam::{Mat4, Quat, Vec3A};
+
+use crate::geometry::{Aabb, Ray};
+
+use super::{bvh::Bvh, collision::collide, gjk, hull::ConvexHull, shape::WorldShape};
+
+// How close a cast has to get to count as a hit
+const CAST_TOLERANCE: f32 = 1e-4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PhysicsHit {
+    pub entity: entity::Identifier,
+    pub point: Vec3A,
+    // Points away from the collider that was hit
+    pub normal: Vec3A,
+    pub distance: f32,
+}
+
+pub struct QueryCollider {
+    pub entity: entity::Identifier,
+    pub shape: WorldShape,
+    pub layers: u32,
+}
+
+// The colliders as of the last physics update. Queries only see colliders on a layer in
+// their mask.
+#[derive(Default)]
+pub struct PhysicsQuery {
+    pub colliders: Vec<QueryCollider>,
+    pub bvh: Bvh,
+}
+
+impl PhysicsQuery {
+    pub fn new(colliders: Vec<QueryCollider>) -> Self {
+        let bvh = Bvh::new(
+            colliders
+                .iter()
+                .enumerate()
+                .map(|(i, collider)| (i, collider.shape.get_bounds()))
+                .collect(),
+        );
+
+        Self { colliders, bvh }
+    }
+
+    fn get_candidates(&self, candidates: Vec<usize>, mask: u32) -> Vec<&QueryCollider> {
+        candidates
+            .into_iter()
+            .map(|i| &self.colliders[i])
+            .filter(|collider| collider.layers & mask != 0)
+            .collect()
+    }
+
+    pub fn raycast(&self, ray: &Ray, max_distance: f32, mask: u32) -> Option<PhysicsHit> {
+        let mut closest: Option<PhysicsHit> = None;
+
+        for collider in self.get_candidates(self.bvh.query_ray(ray, max_distance), mask) {
+            let max_distance = closest.map_or(max_distance, |hit| hit.distance);
+
+            if let Some((distance, normal)) = collider.shape.ray_intersection(ray, max_distance) {
+                closest = Some(PhysicsHit {
+                    entity: collider.entity,
+                    point: ray.at(distance),
+                    normal,
+                    distance,
+                });
+            }
+        }
+
+        closest
+    }
+
+    // The point is where the sphere touches what it hit, the distance how far its center moved
+    pub fn sphere_cast(
+        &self,
+        center: Vec3A,
+        radius: f32,
+        direction: Vec3A,
+        max_distance: f32,
+        mask: u32,
+    ) -> Option<PhysicsHit> {
+        self.shape_cast(
+            &WorldShape::Sphere { center, radius },
+            direction,
+            max_distance,
+            mask,
+            None,
+        )
+    }
+
+    // Like sphere_cast, passing through the colliders of one entity, such as the one casting
+    pub fn sphere_cast_ignoring(
+        &self,
+        center: Vec3A,
+        radius: f32,
+        direction: Vec3A,
+        max_distance: f32,
+        mask: u32,
+        ignore: entity::Identifier,
+    ) -> Option<PhysicsHit> {
+        self.shape_cast(
+            &WorldShape::Sphere { center, radius },
+            direction,
+            max_distance,
+            mask,
+            Some(ignore),
+        )
+    }
+
+    pub fn box_cast(
+        &self,
+        center: Vec3A,
+        half_extents: Vec3A,
+        rotation: Quat,
+        direction: Vec3A,
+        max_distance: f32,
+        mask: u32,
+    ) -> Option<PhysicsHit> {
+        self.shape_cast(
+            &cuboid(center, half_extents, rotation),
+            direction,
+            max_distance,
+            mask,
+            None,
+        )
+    }
+
+    fn shape_cast(
+        &self,
+        shape: &WorldShape,
+        direction: Vec3A,
+        max_distance: f32,
+        mask: u32,
+        ignore: Option<entity::Identifier>,
+    ) -> Option<PhysicsHit> {
+        let direction = direction.normalize();
+
+        let start = shape.get_bounds();
+        let swept = start.union(&Aabb::new(
+            start.min + direction * max_distance,
+            start.max + direction * max_distance,
+        ));
+        let (core, radius) = get_cores(shape, &swept).pop().unwrap();
+
+        let mut closest: Option<PhysicsHit> = None;
+
+        for collider in self.get_candidates(self.bvh.query(&swept), mask) {
+            if Some(collider.entity) == ignore {
+                continue;
+            }
+            let max_distance = closest.map_or(max_distance, |hit| hit.distance);
+
+            // Already touching, so the deepest contact is what was hit
+            let contacts = collide(shape, &collider.shape);
+            if let Some(contact) = contacts.iter().max_by(|a, b| a.depth.total_cmp(&b.depth)) {
+                closest = Some(PhysicsHit {
+                    entity: collider.entity,
+                    point: contact.point,
+                    normal: -contact.normal,
+                    distance: 0.0,
+                });
+                continue;
+            }
+
+            for (other_core, other_radius) in get_cores(&collider.shape, &swept) {
+                let max_distance = closest.map_or(max_distance, |hit| hit.distance);
+
+                if let Some((distance, point, normal)) = cast_core(
+                    &core,
+                    radius,
+                    &other_core,
+                    other_radius,
+                    direction,
+                    max_distance,
+                ) {
+                    closest = Some(PhysicsHit {
+                        entity: collider.entity,
+                        point,
+                        normal,
+                        distance,
+                    });
+                }
+            }
+        }
+
+        closest
+    }
+
+    pub fn overlap(&self, shape: &WorldShape, mask: u32) -> Vec<entity::Identifier> {
+        self.get_candidates(self.bvh.query(&shape.get_bounds()), mask)
+            .into_iter()
+            .filter(|collider| !collide(shape, &collider.shape).is_empty())
+            .map(|collider| collider.entity)
+            .collect()
+    }
+
+    pub fn overlap_sphere(&self, center: Vec3A, radius: f32, mask: u32) -> Vec<entity::Identifier> {
+        self.overlap(&WorldShape::Sphere { center, radius }, mask)
+    }
+
+    pub fn overlap_box(
+        &self,
+        center: Vec3A,
+        half_extents: Vec3A,
+        rotation: Quat,
+        mask: u32,
+    ) -> Vec<entity::Identifier> {
+        self.overlap(&cuboid(center, half_extents, rotation), mask)
+    }
+}
+
+fn cuboid(center: Vec3A, half_extents: Vec3A, rotation: Quat) -> WorldShape {
+    let mat = Mat4::from_rotation_translation(rotation, center.into());
+    WorldShape::Polyhedron(ConvexHull::cuboid(half_extents).transform(&mat))
+}
+
+// The convex parts of a shape as a core of points grown by a radius. Only the triangles of a
+// mesh that are within the bounds are included.
+fn get_cores(shape: &WorldShape, bounds: &Aabb) -> Vec<(Vec<Vec3A>, f32)> {
+    match shape {
+        WorldShape::Sphere { center, radius } => vec![(vec![*center], *radius)],
+        WorldShape::Capsule { a, b, radius } => vec![(vec![*a, *b], *radius)],
+        WorldShape::Polyhedron(hull) => vec![(hull.vertices.clone(), 0.0)],
+        WorldShape::Mesh { mesh, mat } => {
+            let local_bounds = bounds.transform(&mat.inverse());
+
+            mesh.triangles()
+                .filter(|triangle| local_bounds.intersects(&Aabb::from_points(*triangle)))
+                .map(|triangle| {
+                    let triangle = triangle.map(|point| mat.transform_point3a(point));
+                    (triangle.to_vec(), 0.0)
+                })
+                .collect()
+        }
+    }
+}
+
+// Conservative advancement: the core moves up to the plane between the closest points,
+// which it can't cross without touching the other core, until the gap is closed. Returns
+// the distance, the point hit and its normal.
+fn cast_core(
+    core: &[Vec3A],
+    radius: f32,
+    other: &[Vec3A],
+    other_radius: f32,
+    direction: Vec3A,
+    max_distance: f32,
+) -> Option<(f32, Vec3A, Vec3A)> {
+    let mut distance = 0.0;
+
+    for _ in 0..64 {
+        let moved: Vec<Vec3A> = core
+            .iter()
+            .map(|point| *point + direction * distance)
+            .collect();
+        let Some((point, other_point)) = gjk::closest_points(&moved, other) else {
+            return Some((distance, moved[0], -direction));
+        };
+
+        let offset = other_point - point;
+        let normal = offset.normalize();
+        let gap = offset.length() - radius - other_radius;
+        if gap <= CAST_TOLERANCE {
+            return Some((distance, other_point - normal * other_radius, -normal));
+        }
+
+        let closing = direction.dot(normal);
+        if closing <= 1e-6 {
+            return None;
+        }
+
+        distance += gap / closing;
+        if distance > max_distance {
+            return None;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use brood::{entity, Registry, World};
+
+    use super::*;
+    use crate::geometry::TriangleMesh;
+
+    const GROUND: usize = 0;
+    const SPHERE: usize = 1;
+    const BOX: usize = 2;
+    const CAPSULE: usize = 3;
+
+    fn get_query() -> (PhysicsQuery, Vec<entity::Identifier>) {
+        let mut world = World::<Registry!()>::new();
+        let entities: Vec<_> = (0..4).map(|_| world.insert(entity!())).collect();
+
+        let ground = TriangleMesh::new(
+            vec![
+                Vec3A::new(-10.0, 0.0, -10.0),
+                Vec3A::new(10.0, 0.0, -10.0),
+                Vec3A::new(10.0, 0.0, 10.0),
+                Vec3A::new(-10.0, 0.0, 10.0),
+            ],
+            Some(vec![0, 2, 1, 0, 3, 2]),
+        );
+        let shapes = [
+            WorldShape::Mesh {
+                mesh: Arc::new(ground),
+                mat: Mat4::IDENTITY,
+            },
+            WorldShape::Sphere {
+                center: Vec3A::new(0.0, 1.0, 0.0),
+                radius: 1.0,
+            },
+            cuboid(
+                Vec3A::new(4.0, 1.0, 0.0),
+                Vec3A::ONE,
+                Quat::from_rotation_y(45_f32.to_radians()),
+            ),
+            WorldShape::Capsule {
+                a: Vec3A::new(-4.0, 1.0, 0.0),
+                b: Vec3A::new(-4.0, 3.0, 0.0),
+                radius: 0.5,
+            },
+        ];
+        // Only the box is on the second layer
+        let colliders = shapes
+            .into_iter()
+            .enumerate()
+            .map(|(i, shape)| QueryCollider {
+                entity: entities[i],
+                shape,
+                layers: if i == BOX { 2 } else { 1 },
+            })
+            .collect();
+
+        (PhysicsQuery::new(colliders), entities)
+    }
+
+    fn assert_hit(
+        hit: Option<PhysicsHit>,
+        entity: entity::Identifier,
+        distance: f32,
+    ) -> PhysicsHit {
+        let hit = hit.unwrap();
+        assert_eq!(hit.entity, entity);
+        assert!((hit.distance - distance).abs() < 1e-3, "{}", hit.distance);
+        hit
+    }
+
+    #[test]
+    fn raycast() {
+        let (query, entities) = get_query();
+        let down = |x| Ray::new(Vec3A::new(x, 5.0, 0.0), Vec3A::NEG_Y);
+
+        let hit = assert_hit(
+            query.raycast(&down(0.0), 10.0, u32::MAX),
+            entities[SPHERE],
+            3.0,
+        );
+        assert!(hit.normal.abs_diff_eq(Vec3A::Y, 1e-4));
+        assert!(hit.point.abs_diff_eq(Vec3A::new(0.0, 2.0, 0.0), 1e-4));
+
+        assert_hit(
+            query.raycast(&down(-4.0), 10.0, u32::MAX),
+            entities[CAPSULE],
+            1.5,
+        );
+        assert_hit(
+            query.raycast(&down(4.0), 10.0, u32::MAX),
+            entities[BOX],
+            3.0,
+        );
+        assert_hit(query.raycast(&down(4.0), 10.0, 1), entities[GROUND], 5.0);
+
+        assert!(query.raycast(&down(0.0), 2.0, u32::MAX).is_none());
+        assert!(query.raycast(&down(8.0), 10.0, 2).is_none());
+    }
+
+    #[test]
+    fn sphere_cast() {
+        let (query, entities) = get_query();
+
+        let hit = assert_hit(
+            query.sphere_cast(Vec3A::new(0.0, 1.0, -10.0), 0.5, Vec3A::Z, 20.0, u32::MAX),
+            entities[SPHERE],
+            8.5,
+        );
+        assert!(hit.normal.abs_diff_eq(Vec3A::NEG_Z, 1e-3));
+        assert!(hit.point.abs_diff_eq(Vec3A::new(0.0, 1.0, -1.0), 1e-3));
+
+        let hit = assert_hit(
+            query.sphere_cast(Vec3A::new(2.0, 5.0, 8.0), 0.5, Vec3A::NEG_Y, 10.0, u32::MAX),
+            entities[GROUND],
+            4.5,
+        );
+        assert!(hit.normal.abs_diff_eq(Vec3A::Y, 1e-3));
+        assert!(hit.point.abs_diff_eq(Vec3A::new(2.0, 0.0, 8.0), 1e-3));
+
+        // Passes the box by, the ground is too low to be hit
+        assert!(query
+            .sphere_cast(Vec3A::new(6.0, 1.0, -10.0), 0.5, Vec3A::Z, 20.0, u32::MAX)
+            .is_none());
+        assert!(query
+            .sphere_cast(Vec3A::new(0.0, 1.0, -10.0), 0.5, Vec3A::Z, 20.0, 2)
+            .is_none());
+
+        // Starts inside the sphere, which is skipped, and hits the ground
+        assert_hit(
+            query.sphere_cast_ignoring(
+                Vec3A::new(0.0, 1.0, 0.0),
+                0.5,
+                Vec3A::NEG_Y,
+                10.0,
+                u32::MAX,
+                entities[SPHERE],
+            ),
+            entities[GROUND],
+            0.5,
+        );
+    }
+
+    #[test]
+    fn box_cast() {
+        let (query, entities) = get_query();
+        let cast = |center, direction, mask| {
+            query.box_cast(
+                center,
+                Vec3A::splat(0.5),
+                Quat::IDENTITY,
+                direction,
+                20.0,
+                mask,
+            )
+        };
+
+        // The box is turned, so its edge is closest
+        assert_hit(
+            cast(Vec3A::new(4.0, 1.0, -10.0), Vec3A::Z, u32::MAX),
+            entities[BOX],
+            9.5 - 2_f32.sqrt(),
+        );
+        assert!(cast(Vec3A::new(4.0, 1.0, -10.0), Vec3A::Z, 1).is_none());
+
+        let hit = assert_hit(
+            cast(Vec3A::new(-2.0, 5.0, 6.0), Vec3A::NEG_Y, u32::MAX),
+            entities[GROUND],
+            4.5,
+        );
+        assert!(hit.normal.abs_diff_eq(Vec3A::Y, 1e-3));
+
+        // Starting inside counts as a hit right away
+        assert_hit(
+            cast(Vec3A::new(0.0, 1.0, 0.0), Vec3A::X, u32::MAX),
+            entities[SPHERE],
+            0.0,
+        );
+    }
+
+    #[test]
+    fn overlap() {
+        let (query, entities) = get_query();
+
+        let mut touching = query.overlap_sphere(Vec3A::ZERO, 0.5, u32::MAX);
+        touching.sort_by_key(|entity| entities.iter().position(|other| other == entity));
+        assert_eq!(touching, vec![entities[GROUND], entities[SPHERE]]);
+        assert!(query.overlap_sphere(Vec3A::ZERO, 0.5, 2).is_empty());
+
+        let rotation = Quat::from_rotation_y(45_f32.to_radians());
+        assert_eq!(
+            query.overlap_box(
+                Vec3A::new(4.0, 2.5, 0.0),
+                Vec3A::splat(0.6),
+                rotation,
+                u32::MAX
+            ),
+            vec![entities[BOX]]
+        );
+        assert!(query
+            .overlap_box(Vec3A::new(4.0, 2.5, 0.0), Vec3A::splat(0.6), rotation, 1)
+            .is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use glam::{Mat3A, Mat4, Vec3A};
+
+use crate::{
+    components::{collider::ColliderShape, transform::TransformComponent},
+    geometry::{Aabb, Ray, TriangleMesh},
+};
+
+use super::{collision::closest_point_on_segment, hull::ConvexHull};
+
+// A collider placed in the world, scale included
+pub enum WorldShape {
+    Sphere { center: Vec3A, radius: f32 },
+    // The segment between the centers of the caps
+    Capsule { a: Vec3A, b: Vec3A, radius: f32 },
+    Polyhedron(ConvexHull),
+    // Triangles stay in mesh space, only the ones near the other shape get transformed
+    Mesh { mesh: Arc<TriangleMesh>, mat: Mat4 },
+}
+
+impl WorldShape {
+    pub fn new(shape: &ColliderShape, transform: &TransformComponent) -> Self {
+        let mat = transform.get_mat();
+        let scale = transform.scale.abs();
+
+        match shape {
+            ColliderShape::Sphere { radius } => Self::Sphere {
+                center: transform.translation,
+                radius: radius * scale.max_element(),
+            },
+            ColliderShape::Box { half_extents } => {
+                Self::Polyhedron(ConvexHull::cuboid(*half_extents).transform(&mat))
+            }
+            ColliderShape::Capsule {
+                radius,
+                half_height,
+            } => {
+                let axis = transform.rotation * Vec3A::Y * *half_height * scale.y;
+
+                Self::Capsule {
+                    a: transform.translation - axis,
+                    b: transform.translation + axis,
+                    radius: radius * scale.x.max(scale.z),
+                }
+            }
+            ColliderShape::ConvexHull(hull) => Self::Polyhedron(hull.transform(&mat)),
+            ColliderShape::TriangleMesh(mesh) => Self::Mesh {
+                mesh: mesh.clone(),
+                mat,
+            },
+        }
+    }
+
+    pub fn get_bounds(&self) -> Aabb {
+        match self {
+            Self::Sphere { center, radius } => Aabb::new(
+                *center - Vec3A::splat(*radius),
+                *center + Vec3A::splat(*radius),
+            ),
+            Self::Capsule { a, b, radius } => Aabb::new(
+                a.min(*b) - Vec3A::splat(*radius),
+                a.max(*b) + Vec3A::splat(*radius),
+            ),
+            Self::Polyhedron(hull) => hull.get_bounds(),
+            Self::Mesh { mesh, mat } => mesh.bounds.transform(mat),
+        }
+    }
+
+    // Distance to the hit and the normal there. Rays starting inside hit right away,
+    // against their direction.
+    pub fn ray_intersection(&self, ray: &Ray, max_distance: f32) -> Option<(f32, Vec3A)> {
+        let hit = match self {
+            Self::Sphere { center, radius } => ray_sphere_intersection(ray, *center, *radius),
+            Self::Capsule { a, b, radius } => {
+                if closest_point_on_segment(*a, *b, ray.origin).distance(ray.origin) <= *radius {
+                    return Some((0.0, -ray.direction));
+                }
+
+                let axis = (*b - *a).normalize_or_zero();
+                let offset = ray.origin - *a;
+                let across = |v: Vec3A| v - axis * v.dot(axis);
+                let (offset_across, direction_across) = (across(offset), across(ray.direction));
+
+                // The side, as an infinite cylinder cut to the length of the segment
+                let qa = direction_across.length_squared();
+                let qb = offset_across.dot(direction_across);
+                let qc = offset_across.length_squared() - radius * radius;
+                let discriminant = qb * qb - qa * qc;
+                let side = (qa > 1e-12 && discriminant >= 0.0)
+                    .then(|| (-qb - discriminant.sqrt()) / qa)
+                    .filter(|t| {
+                        let along = (offset + ray.direction * *t).dot(axis);
+                        *t >= 0.0 && along >= 0.0 && along <= a.distance(*b)
+                    })
+                    .map(|t| (t, (offset_across + direction_across * t) / *radius));
+
+                [
+                    side,
+                    ray_sphere_intersection(ray, *a, *radius),
+                    ray_sphere_intersection(ray, *b, *radius),
+                ]
+                .into_iter()
+                .flatten()
+                .min_by(|x, y| x.0.total_cmp(&y.0))
+            }
+            Self::Polyhedron(hull) => {
+                let (mut enter, mut exit, mut normal) = (0.0, max_distance, -ray.direction);
+
+                for face in &hull.faces {
+                    let distance = face.normal.dot(ray.origin - hull.vertices[face.indices[0]]);
+                    let speed = face.normal.dot(ray.direction);
+
+                    if speed.abs() < 1e-12 {
+                        if distance > 0.0 {
+                            return None;
+                        }
+                    } else if speed < 0.0 {
+                        if -distance / speed > enter {
+                            enter = -distance / speed;
+                            normal = face.normal;
+                        }
+                    } else {
+                        exit = f32::min(exit, -distance / speed);
+                    }
+
+                    if enter > exit {
+                        return None;
+                    }
+                }
+
+                Some((enter, normal))
+            }
+            Self::Mesh { mesh, mat } => {
+                // Not renormalizing the direction keeps the hit distance in world units
+                let local_ray = ray.transform(&mat.inverse());
+
+                mesh.ray_intersection(&local_ray, max_distance)
+                    .map(|(distance, normal)| {
+                        let normal_mat = Mat3A::from_mat4(*mat).inverse().transpose();
+                        (distance, (normal_mat * normal).normalize_or_zero())
+                    })
+            }
+        };
+
+        hit.filter(|(distance, _)| *distance <= max_distance)
+    }
+}
+
+fn ray_sphere_intersection(ray: &Ray, center: Vec3A, radius: f32) -> Option<(f32, Vec3A)> {
+    let offset = ray.origin - center;
+    let along = offset.dot(ray.direction);
+    let outside = offset.length_squared() - radius * radius;
+
+    if outside <= 0.0 {
+        return Some((0.0, -ray.direction));
+    }
+
+    let discriminant = along * along - outside;
+    if discriminant < 0.0 || along > 0.0 {
+        return None;
+    }
+
+    let distance = -along - discriminant.sqrt();
+    Some((distance, (ray.at(distance) - center) / radius))
+}
+
+impl ColliderShape {
+    // Diagonal of the inertia tensor in local space, hulls and meshes use their bounds
+    pub fn get_inertia(&self, mass: f32, scale: Vec3A) -> Vec3A {
+        let scale = scale.abs();
+        let cuboid = |half_extents: Vec3A| {
+            let squared = half_extents * half_extents;
+            Vec3A::new(
+                squared.y + squared.z,
+                squared.x + squared.z,
+                squared.x + squared.y,
+            ) * mass
+                / 3.0
+        };
+
+        match self {
+            Self::Sphere { radius } => {
+                let radius = radius * scale.max_element();
+                Vec3A::splat(0.4 * mass * radius * radius)
+            }
+            Self::Box { half_extents } => cuboid(*half_extents * scale),
+            Self::Capsule {
+                radius,
+                half_height,
+            } => {
+                let radius = radius * scale.x.max(scale.z);
+                let half_height = half_height * scale.y;
+
+                // Split between the cylinder and the caps by volume
+                let cylinder_volume = 2.0 * half_height;
+                let caps_volume = 4.0 / 3.0 * radius;
+                let cylinder = mass * cylinder_volume / (cylinder_volume + caps_volume);
+                let caps = mass - cylinder;
+
+                let squared = radius * radius;
+                let along = cylinder * squared * 0.5 + caps * squared * 0.4;
+                let across = cylinder * (squared * 0.25 + half_height * half_height / 3.0)
+                    + caps
+                        * (squared * 0.4
+                            + half_height * half_height
+                            + 0.375 * half_height * radius);
+
+                Vec3A::new(across, along, across)
+            }
+            Self::ConvexHull(hull) => cuboid(hull.get_bounds().half_extents() * scale),
+            Self::TriangleMesh(mesh) => cuboid(mesh.bounds.half_extents() * scale),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use glam::{Mat3A, Quat, Vec3A};
+
+use crate::{
+    components::{
+        collider::ColliderComponent,
+        rigid_body::{BodyType, RigidBodyComponent},
+        transform::TransformComponent,
+    },
+    geometry::Aabb,
+};
+
+use super::{bvh::Bvh, collision::collide, shape::WorldShape};
+
+// Penetration that is left alone, so resting contacts don't come and go every step
+const SLOP: f32 = 0.01;
+// Fraction of the remaining penetration fixed per step
+const BAUMGARTE: f32 = 0.2;
+// Slower impacts don't bounce
+const RESTITUTION_THRESHOLD: f32 = 1.0;
+
+pub struct PhysicsBody<'a> {
+    pub transform: &'a mut TransformComponent,
+    pub rigid_body: Option<&'a mut RigidBodyComponent>,
+    pub collider: Option<&'a ColliderComponent>,
+}
+
+impl PhysicsBody<'_> {
+    fn get_body_type(&self) -> BodyType {
+        self.rigid_body
+            .as_ref()
+            .map_or(BodyType::Static, |rigid_body| rigid_body.body_type)
+    }
+}
+
+struct BodyState {
+    inverse_mass: f32,
+    inverse_inertia: Mat3A,
+    velocity: Vec3A,
+    angular_velocity: Vec3A,
+}
+
+impl BodyState {
+    fn get_point_velocity(&self, offset: Vec3A) -> Vec3A {
+        self.velocity + self.angular_velocity.cross(offset)
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec3A, offset: Vec3A) {
+        self.velocity += impulse * self.inverse_mass;
+        self.angular_velocity += self.inverse_inertia * offset.cross(impulse);
+    }
+}
+
+struct ContactConstraint {
+    a: usize,
+    b: usize,
+    normal: Vec3A,
+    tangents: [Vec3A; 2],
+    offset_a: Vec3A,
+    offset_b: Vec3A,
+    normal_mass: f32,
+    tangent_masses: [f32; 2],
+    // Target separating speed, from bouncing and penetration
+    bias: f32,
+    friction: f32,
+    normal_impulse: f32,
+    tangent_impulses: [f32; 2],
+}
+
+// Candidate pairs whose bounds overlap
+pub fn find_pairs(bounds: &[Option<Aabb>]) -> Vec<(usize, usize)> {
+    let items: Vec<_> = bounds
+        .iter()
+        .enumerate()
+        .filter_map(|(i, bounds)| bounds.map(|bounds| (i, bounds)))
+        .collect();
+    let bvh = Bvh::new(items.clone());
+
+    items
+        .iter()
+        .flat_map(|(a, bounds)| {
+            bvh.query(bounds)
+                .into_iter()
+                .filter(move |b| b > a)
+                .map(move |b| (*a, b))
+        })
+        .collect()
+}
+
+// One fixed step: forces, contacts with sequential impulses, then integration
+pub fn step(bodies: &mut [PhysicsBody], gravity: Vec3A, iterations: u32, dt: f32) {
+    let mut states: Vec<BodyState> = bodies
+        .iter_mut()
+        .map(|body| {
+            let Some(rigid_body) = &mut body.rigid_body else {
+                return BodyState {
+                    inverse_mass: 0.0,
+                    inverse_inertia: Mat3A::ZERO,
+                    velocity: Vec3A::ZERO,
+                    angular_velocity: Vec3A::ZERO,
+                };
+            };
+
+            let inverse_mass = rigid_body.get_inverse_mass();
+            let inverse_inertia = match body.collider {
+                Some(collider) if inverse_mass > 0.0 => {
+                    let inertia = collider
+                        .shape
+                        .get_inertia(rigid_body.mass, body.transform.scale);
+                    let rotation = Mat3A::from_quat(body.transform.rotation);
+                    let inverse =
+                        Vec3A::select(inertia.cmpgt(Vec3A::ZERO), inertia.recip(), Vec3A::ZERO);
+
+                    rotation * Mat3A::from_diagonal(inverse.into()) * rotation.transpose()
+                }
+                _ => Mat3A::ZERO,
+            };
+
+            if rigid_body.body_type == BodyType::Dynamic {
+                rigid_body.linear_velocity +=
+                    (gravity * rigid_body.gravity_scale + rigid_body.force * inverse_mass) * dt;
+                rigid_body.angular_velocity += inverse_inertia * rigid_body.torque * dt;
+                rigid_body.linear_velocity /= 1.0 + rigid_body.linear_damping * dt;
+                rigid_body.angular_velocity /= 1.0 + rigid_body.angular_damping * dt;
+            }
+
+            BodyState {
+                inverse_mass,
+                inverse_inertia,
+                velocity: rigid_body.linear_velocity,
+                angular_velocity: rigid_body.angular_velocity,
+            }
+        })
+        .collect();
+
+    let shapes: Vec<Option<WorldShape>> = bodies
+        .iter()
+        .map(|body| {
+            body.collider
+                .map(|collider| WorldShape::new(&collider.shape, body.transform))
+        })
+        .collect();
+    let bounds: Vec<_> = shapes
+        .iter()
+        .map(|shape| shape.as_ref().map(WorldShape::get_bounds))
+        .collect();
+
+    let mut constraints = Vec::new();
+
+    for (a, b) in find_pairs(&bounds) {
+        // At least one of them has to be pushed around
+        if bodies[a].get_body_type() != BodyType::Dynamic
+            && bodies[b].get_body_type() != BodyType::Dynamic
+        {
+            continue;
+        }
+
+        let (collider_a, collider_b) = (bodies[a].collider.unwrap(), bodies[b].collider.unwrap());
+        let friction = (collider_a.friction * collider_b.friction).sqrt();
+        let restitution = collider_a.restitution.max(collider_b.restitution);
+
+        let contacts = collide(shapes[a].as_ref().unwrap(), shapes[b].as_ref().unwrap());
+
+        for contact in contacts {
+            let offset_a = contact.point - bodies[a].transform.translation;
+            let offset_b = contact.point - bodies[b].transform.translation;
+            let (state_a, state_b) = (&states[a], &states[b]);
+
+            let effective_mass = |direction: Vec3A| {
+                let angular = |state: &BodyState, offset: Vec3A| {
+                    (state.inverse_inertia * offset.cross(direction))
+                        .cross(offset)
+                        .dot(direction)
+                };
+                let sum = state_a.inverse_mass
+                    + state_b.inverse_mass
+                    + angular(state_a, offset_a)
+                    + angular(state_b, offset_b);
+
+                if sum > 0.0 {
+                    1.0 / sum
+                } else {
+                    0.0
+                }
+            };
+
+            let tangent = contact.normal.any_orthonormal_vector();
+            let tangents = [tangent, contact.normal.cross(tangent)];
+
+            let approach = (state_b.get_point_velocity(offset_b)
+                - state_a.get_point_velocity(offset_a))
+            .dot(contact.normal);
+            let bounce = if approach < -RESTITUTION_THRESHOLD {
+                -restitution * approach
+            } else {
+                0.0
+            };
+            let push = BAUMGARTE / dt * (contact.depth - SLOP).max(0.0);
+
+            constraints.push(ContactConstraint {
+                a,
+                b,
+                normal: contact.normal,
+                tangents,
+                offset_a,
+                offset_b,
+                normal_mass: effective_mass(contact.normal),
+                tangent_masses: tangents.map(effective_mass),
+                bias: bounce.max(push),
+                friction,
+                normal_impulse: 0.0,
+                tangent_impulses: [0.0; 2],
+            });
+        }
+    }
+
+    for _ in 0..iterations {
+        for constraint in &mut constraints {
+            let relative_velocity = |states: &[BodyState]| {
+                states[constraint.b].get_point_velocity(constraint.offset_b)
+                    - states[constraint.a].get_point_velocity(constraint.offset_a)
+            };
+            let apply = |states: &mut [BodyState], impulse: Vec3A| {
+                states[constraint.a].apply_impulse(-impulse, constraint.offset_a);
+                states[constraint.b].apply_impulse(impulse, constraint.offset_b);
+            };
+
+            let speed = relative_velocity(&states).dot(constraint.normal);
+            let impulse = (constraint.bias - speed) * constraint.normal_mass;
+            let total = (constraint.normal_impulse + impulse).max(0.0);
+            apply(
+                &mut states,
+                constraint.normal * (total - constraint.normal_impulse),
+            );
+            constraint.normal_impulse = total;
+
+            let limit = constraint.friction * constraint.normal_impulse;
+            for i in 0..2 {
+                let speed = relative_velocity(&states).dot(constraint.tangents[i]);
+                let impulse = -speed * constraint.tangent_masses[i];
+                let total = (constraint.tangent_impulses[i] + impulse).clamp(-limit, limit);
+                apply(
+                    &mut states,
+                    constraint.tangents[i] * (total - constraint.tangent_impulses[i]),
+                );
+                constraint.tangent_impulses[i] = total;
+            }
+        }
+    }
+
+    for (body, state) in bodies.iter_mut().zip(states) {
+        let Some(rigid_body) = &mut body.rigid_body else {
+            continue;
+        };
+        if rigid_body.body_type == BodyType::Static {
+            continue;
+        }
+
+        if rigid_body.body_type == BodyType::Dynamic {
+            rigid_body.linear_velocity = state.velocity;
+            rigid_body.angular_velocity = state.angular_velocity;
+        }
+
+        let spin = rigid_body.angular_velocity;
+        let transform = &mut body.transform;
+        transform.translation += rigid_body.linear_velocity * dt;
+        transform.rotation = (transform.rotation
+            + Quat::from_xyzw(spin.x, spin.y, spin.z, 0.0) * transform.rotation * (0.5 * dt))
+            .normalize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_rests_on_ground() {
+        let ground_collider = ColliderComponent::cuboid(Vec3A::new(10.0, 1.0, 10.0));
+        let box_collider = ColliderComponent::cuboid(Vec3A::splat(0.5));
+        let mut ground = TransformComponent::from_position(0.0, -1.0, 0.0);
+        let mut cube = TransformComponent::from_position(0.0, 0.5, 0.0);
+        let mut rigid_body = RigidBodyComponent::dynamic(1.0);
+
+        for _ in 0..300 {
+            let mut bodies = [
+                PhysicsBody {
+                    transform: &mut ground,
+                    rigid_body: None,
+                    collider: Some(&ground_collider),
+                },
+                PhysicsBody {
+                    transform: &mut cube,
+                    rigid_body: Some(&mut rigid_body),
+                    collider: Some(&box_collider),
+                },
+            ];
+            step(&mut bodies, Vec3A::new(0.0, -9.81, 0.0), 10, 1.0 / 60.0);
+        }
+
+        // Sinks no further than the slop
+        assert!((cube.translation.y - 0.5).abs() < SLOP);
+        assert!(cube.translation.x.abs() < 1e-3 && cube.translation.z.abs() < 1e-3);
+        assert!(cube.rotation.angle_between(Quat::IDENTITY) < 1e-3);
+        assert!(rigid_body.linear_velocity.length() < 0.05);
+        assert!(rigid_body.angular_velocity.length() < 0.05);
+        assert_eq!(ground.translation, Vec3A::new(0.0, -1.0, 0.0));
+    }
+}
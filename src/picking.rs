@@ -0,0 +1,51 @@
+use brood::entity;
+use glam::{Mat3A, Vec3A};
+
+use crate::{
+    components::{draw::DrawComponent, transform::TransformComponent},
+    geometry::Ray,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PickHit {
+    pub entity: entity::Identifier,
+    pub point: Vec3A,
+    pub normal: Vec3A,
+    pub distance: f32,
+}
+
+pub fn raycast_drawables<'a>(
+    ray: &Ray,
+    max_distance: f32,
+    drawables: impl IntoIterator<
+        Item = (
+            entity::Identifier,
+            &'a TransformComponent,
+            &'a DrawComponent,
+        ),
+    >,
+) -> Option<PickHit> {
+    let mut closest: Option<PickHit> = None;
+
+    for (entity, transform, draw) in drawables {
+        let Some(shape) = draw.inner.get_shape() else {
+            continue;
+        };
+
+        let model = transform.get_mat();
+        // Not renormalizing the direction keeps the hit distance in world units
+        let local_ray = ray.transform(&model.inverse());
+        let max_distance = closest.map_or(max_distance, |hit| hit.distance);
+
+        if let Some((distance, normal)) = shape.ray_intersection(&local_ray, max_distance) {
+            closest = Some(PickHit {
+                entity,
+                point: ray.at(distance),
+                normal: (Mat3A::from_mat4(model).inverse().transpose() * normal).normalize_or_zero(),
+                distance,
+            });
+        }
+    }
+
+    closest
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+
+use crate::resources::input::InputEvent;
+
+#[derive(Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub dt: Duration,
+    pub events: Vec<InputEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InputRecording {
+    pub window_size: PhysicalSize<u32>,
+    pub focused: bool,
+    pub frames: Vec<RecordedFrame>,
+    #[serde(skip)]
+    pending: Vec<InputEvent>,
+}
+
+impl InputRecording {
+    pub fn new(window_size: PhysicalSize<u32>, focused: bool) -> Self {
+        Self {
+            window_size,
+            focused,
+            frames: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> bincode::Result<Self> {
+        bincode::deserialize_from(BufReader::new(File::open(path)?))
+    }
+
+    // Finishes the recording first
+    pub fn save(&mut self, path: &Path) -> bincode::Result<()> {
+        self.finish();
+        bincode::serialize_into(BufWriter::new(File::create(path)?), self)
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.pending.push(event);
+    }
+
+    pub fn end_frame(&mut self, dt: Duration) {
+        self.frames.push(RecordedFrame {
+            dt,
+            events: std::mem::take(&mut self.pending),
+        });
+    }
+
+    // Events received after the last frame ended get a frame of their own, with no time
+    // passing, so they aren't lost
+    pub fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            self.end_frame(Duration::ZERO);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_after_the_last_frame_are_kept() {
+        let mut recording = InputRecording::new(PhysicalSize::new(800, 600), true);
+        recording.push(InputEvent::Scroll(0.0, 1.0));
+        recording.end_frame(Duration::from_millis(16));
+        recording.push(InputEvent::Focused(false));
+
+        let path = std::env::temp_dir().join("events_after_the_last_frame_are_kept.bin");
+        recording.save(&path).unwrap();
+        let frames = InputRecording::load(&path).unwrap().frames;
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].dt, Duration::ZERO);
+        assert_eq!(frames[1].events, [InputEvent::Focused(false)]);
+
+        // Nothing left over, so no empty frame
+        recording.finish();
+        assert_eq!(recording.frames.len(), 2);
+    }
+}
@@ -0,0 +1,142 @@
+use glam::Vec2;
+
+pub const ATLAS_PADDING: u32 = 2;
+
+// Where a packed texture ended up, in UV space of the whole atlas
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct AtlasRegion {
+    pub offset: Vec2,
+    pub size: Vec2,
+}
+
+impl AtlasRegion {
+    // Maps UVs in the 0..1 range of the original texture into the atlas
+    pub fn remap(&self, uv: Vec2) -> Vec2 {
+        self.offset + uv * self.size
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct TextureAtlas {
+    pub width: u32,
+    pub height: u32,
+    // In the order the textures were given
+    pub regions: Vec<AtlasRegion>,
+}
+
+impl TextureAtlas {
+    // Shelf packing, tallest first. Returns the layout and the pixel position of each texture.
+    pub fn pack(sizes: &[(u32, u32)], padding: u32) -> (Self, Vec<(u32, u32)>) {
+        let padded = |(width, height): (u32, u32)| (width + padding * 2, height + padding * 2);
+
+        let area: u32 = sizes
+            .iter()
+            .map(|size| padded(*size))
+            .map(|(width, height)| width * height)
+            .sum();
+        let widest = sizes.iter().map(|size| padded(*size).0).max().unwrap_or(1);
+        let width = ((area as f32).sqrt().ceil() as u32)
+            .max(widest)
+            .next_power_of_two();
+
+        let mut order: Vec<_> = (0..sizes.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(sizes[*i].1));
+
+        let mut positions = vec![(0, 0); sizes.len()];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for i in order {
+            let (padded_width, padded_height) = padded(sizes[i]);
+
+            if x + padded_width > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+
+            positions[i] = (x + padding, y + padding);
+            x += padded_width;
+            shelf_height = shelf_height.max(padded_height);
+        }
+
+        let height = (y + shelf_height).max(1).next_power_of_two();
+
+        let regions = sizes
+            .iter()
+            .zip(&positions)
+            .map(|((region_width, region_height), (x, y))| AtlasRegion {
+                offset: Vec2::new(*x as f32 / width as f32, *y as f32 / height as f32),
+                size: Vec2::new(
+                    *region_width as f32 / width as f32,
+                    *region_height as f32 / height as f32,
+                ),
+            })
+            .collect();
+
+        (
+            Self {
+                width,
+                height,
+                regions,
+            },
+            positions,
+        )
+    }
+
+    // Packs RGBA8 images, rows bottom first like the rest of the texture data. The padding
+    // repeats the edge pixels of each image so filtering doesn't pick up its neighbours, and
+    // stays clear for empty images.
+    pub fn build(images: &[(u32, u32, &[u8])], padding: u32) -> (Self, Vec<u8>) {
+        let sizes: Vec<_> = images
+            .iter()
+            .map(|(width, height, _)| (*width, *height))
+            .collect();
+        let (atlas, positions) = Self::pack(&sizes, padding);
+
+        let mut data = vec![0; (atlas.width * atlas.height * 4) as usize];
+
+        for ((width, height, pixels), (x, y)) in images.iter().zip(positions) {
+            // Nothing to copy, or to repeat into the padding
+            if *width == 0 || *height == 0 {
+                continue;
+            }
+
+            for atlas_y in y - padding..y + height + padding {
+                for atlas_x in x - padding..x + width + padding {
+                    let source_x = atlas_x.clamp(x, x + width - 1) - x;
+                    let source_y = atlas_y.clamp(y, y + height - 1) - y;
+
+                    let source = ((source_y * width + source_x) * 4) as usize;
+                    let target = ((atlas_y * atlas.width + atlas_x) * 4) as usize;
+
+                    data[target..target + 4].copy_from_slice(&pixels[source..source + 4]);
+                }
+            }
+        }
+
+        (atlas, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_images() {
+        let red = [255, 0, 0, 255].repeat(4);
+        let images: [(u32, u32, &[u8]); 3] = [(0, 0, &[]), (2, 2, &red), (3, 0, &[])];
+
+        let (atlas, data) = TextureAtlas::build(&images, ATLAS_PADDING);
+
+        assert_eq!(atlas.regions.len(), 3);
+        assert_eq!(atlas.regions[0].size, Vec2::ZERO);
+        assert_eq!(atlas.regions[2].size.y, 0.0);
+
+        let region = atlas.regions[1];
+        let x = (region.offset.x * atlas.width as f32) as usize;
+        let y = (region.offset.y * atlas.height as f32) as usize;
+        let pixel = (y * atlas.width as usize + x) * 4;
+        assert_eq!(data[pixel..pixel + 4], [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::f32::consts::PI;
+
+use glam::Vec3;
+use image::Rgb32FImage;
+
+use super::Skybox;
+
+// Faces are in OpenGL order (+X, -X, +Y, -Y, +Z, -Z) with rows laid out the way OpenGL expects
+pub struct CubeFaces {
+    pub size: u32,
+    pub faces: [Vec<f32>; 6],
+}
+
+impl CubeFaces {
+    pub fn load(skybox: &Skybox) -> Self {
+        match skybox {
+            Skybox::Faces(paths) => {
+                let images = paths.clone().map(|path| image::open(path).unwrap().to_rgb32f());
+                let size = images[0].height();
+
+                Self::from_fn(size, |dir| sample_skybox_faces(&images, dir))
+            }
+            Skybox::Equirectangular(path) => {
+                let image = image::open(path).unwrap().to_rgb32f();
+                let size = image.height() / 2;
+
+                Self::from_fn(size, |dir| sample_equirectangular(&image, dir))
+            }
+        }
+    }
+
+    pub fn from_fn(size: u32, sample: impl Fn(Vec3) -> [f32; 3]) -> Self {
+        let faces = std::array::from_fn(|face| {
+            let mut data = Vec::with_capacity((size * size * 3) as usize);
+
+            for y in 0..size {
+                for x in 0..size {
+                    let (dir, _) = texel_direction(face, x, y, size);
+                    data.extend_from_slice(&sample(dir));
+                }
+            }
+
+            data
+        });
+
+        Self { size, faces }
+    }
+
+    // Order 2 spherical harmonics of the cosine convolved radiance, divided by pi so that
+    // evaluating them gives the diffuse light directly. Packed per color channel.
+    pub fn irradiance_sh(&self) -> [[[f32; 3]; 3]; 3] {
+        let mut coefficients = [Vec3::ZERO; 9];
+        let mut total_weight = 0.0;
+
+        for (face, data) in self.faces.iter().enumerate() {
+            for y in 0..self.size {
+                for x in 0..self.size {
+                    let (dir, weight) = texel_direction(face, x, y, self.size);
+                    let i = ((y * self.size + x) * 3) as usize;
+                    let color = Vec3::from_slice(&data[i..i + 3]);
+
+                    for (coefficient, basis) in coefficients.iter_mut().zip(sh_basis(dir)) {
+                        *coefficient += color * basis * weight;
+                    }
+                    total_weight += weight;
+                }
+            }
+        }
+
+        // Normalize the texel solid angles so they sum to exactly 4 pi
+        let normalization = 4.0 * PI / total_weight;
+        let band_factors = [1.0, 2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 0.25, 0.25, 0.25, 0.25, 0.25];
+
+        for (coefficient, band_factor) in coefficients.iter_mut().zip(band_factors) {
+            *coefficient *= normalization * band_factor;
+        }
+
+        pack_sh(&coefficients)
+    }
+}
+
+// Spherical harmonics that evaluate to a constant everywhere
+pub fn constant_sh(value: f32) -> [[[f32; 3]; 3]; 3] {
+    let mut coefficients = [Vec3::ZERO; 9];
+    coefficients[0] = Vec3::splat(value / sh_basis(Vec3::Z)[0]);
+
+    pack_sh(&coefficients)
+}
+
+fn pack_sh(coefficients: &[Vec3; 9]) -> [[[f32; 3]; 3]; 3] {
+    std::array::from_fn(|channel| {
+        std::array::from_fn(|column| {
+            std::array::from_fn(|row| coefficients[column * 3 + row][channel])
+        })
+    })
+}
+
+fn sh_basis(dir: Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * dir.y,
+        0.488603 * dir.z,
+        0.488603 * dir.x,
+        1.092548 * dir.x * dir.y,
+        1.092548 * dir.y * dir.z,
+        0.315392 * (3.0 * dir.z * dir.z - 1.0),
+        1.092548 * dir.x * dir.z,
+        0.546274 * (dir.x * dir.x - dir.y * dir.y),
+    ]
+}
+
+// Returns the direction through the center of a texel and the solid angle it covers
+fn texel_direction(face: usize, x: u32, y: u32, size: u32) -> (Vec3, f32) {
+    let s = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let t = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+
+    let dir = match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    };
+
+    let texel_size = 2.0 / size as f32;
+    let weight = texel_size * texel_size / (1.0 + s * s + t * t).powf(1.5);
+
+    (dir.normalize(), weight)
+}
+
+// Faces are right, left, top, bottom, front, back as seen from the inside, front facing +Z
+fn sample_skybox_faces(images: &[Rgb32FImage; 6], dir: Vec3) -> [f32; 3] {
+    let abs = dir.abs();
+
+    let (face, u, v) = if abs.x >= abs.y && abs.x >= abs.z {
+        if dir.x > 0.0 {
+            (0, -dir.z / abs.x, -dir.y / abs.x)
+        } else {
+            (1, dir.z / abs.x, -dir.y / abs.x)
+        }
+    } else if abs.y >= abs.z {
+        if dir.y > 0.0 {
+            (2, dir.x / abs.y, dir.z / abs.y)
+        } else {
+            (3, dir.x / abs.y, -dir.z / abs.y)
+        }
+    } else if dir.z > 0.0 {
+        (4, dir.x / abs.z, -dir.y / abs.z)
+    } else {
+        (5, -dir.x / abs.z, -dir.y / abs.z)
+    };
+
+    sample_image(&images[face], (u + 1.0) * 0.5, (v + 1.0) * 0.5)
+}
+
+fn sample_equirectangular(image: &Rgb32FImage, dir: Vec3) -> [f32; 3] {
+    let u = dir.x.atan2(dir.z) / (2.0 * PI) + 0.5;
+    let v = 0.5 - dir.y.asin() / PI;
+
+    sample_image(image, u, v)
+}
+
+fn sample_image(image: &Rgb32FImage, u: f32, v: f32) -> [f32; 3] {
+    let x = ((u * image.width() as f32) as u32).min(image.width() - 1);
+    let y = ((v * image.height() as f32) as u32).min(image.height() - 1);
+
+    image.get_pixel(x, y).0
+}
@@ -0,0 +1,206 @@
+use std::{
+    borrow::Cow,
+    cell::{Ref, RefCell},
+    collections::HashMap,
+    path::Path,
+    rc::{Rc, Weak},
+};
+
+use brood::World;
+
+use crate::{
+    components::{draw::DrawComponent, Registry},
+    geometry::TriangleMesh,
+    resources::Resources,
+    AtlasId, DrawData, DrawDescriptor, Mesh, MeshId, RenderTargetId, Renderer, Skybox,
+    TextureArrayId, TextureSettings,
+};
+
+use super::{
+    atlas::{TextureAtlas, ATLAS_PADDING},
+    mesh_data::MeshData,
+};
+
+// Only the shape is kept, so picking and mesh colliders work the same as when rendering
+struct HeadlessDrawData {
+    shape: Rc<RefCell<TriangleMesh>>,
+}
+
+impl DrawData for HeadlessDrawData {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn get_shape(&self) -> Option<Ref<'_, TriangleMesh>> {
+        Some(self.shape.borrow())
+    }
+}
+
+#[derive(Default)]
+pub struct HeadlessRenderer {
+    render_target_count: u32,
+    atlas_count: u32,
+    texture_array_count: u32,
+    mesh_count: u32,
+    shapes: HashMap<Mesh, Weak<RefCell<TriangleMesh>>>,
+    custom_shapes: HashMap<MeshId, Rc<RefCell<TriangleMesh>>>,
+}
+
+impl HeadlessRenderer {
+    fn load_shape(&mut self, mesh: &Mesh) -> Rc<RefCell<TriangleMesh>> {
+        if let Mesh::Custom(id) = mesh {
+            if let Some(shape) = self.custom_shapes.get(id) {
+                return shape.clone();
+            }
+
+            eprintln!("Warning: mesh {id:?} doesn't exist, using a cube instead");
+            return self.load_shape(&Mesh::Cube);
+        }
+
+        if let Some(shape) = self.shapes.get(mesh).and_then(Weak::upgrade) {
+            return shape;
+        }
+
+        let shape = Rc::new(RefCell::new(mesh.load_data().get_triangle_mesh()));
+        self.shapes.insert(mesh.clone(), Rc::downgrade(&shape));
+
+        shape
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn render(&mut self, _world: &mut World<Registry, Resources>) {}
+
+    fn load(&mut self, descriptor: &DrawDescriptor) -> DrawComponent {
+        DrawComponent {
+            inner: Box::new(HeadlessDrawData {
+                shape: self.load_shape(&descriptor.mesh),
+            }),
+        }
+    }
+
+    fn create_render_target(&mut self, _width: u32, _height: u32) -> RenderTargetId {
+        self.render_target_count += 1;
+        RenderTargetId(self.render_target_count - 1)
+    }
+
+    fn set_skybox(&mut self, _skybox: Option<&Skybox>) {}
+
+    // The layout is still computed so UVs come out the same as with a real renderer
+    fn create_atlas(
+        &mut self,
+        textures: &[Cow<'static, Path>],
+        _settings: &TextureSettings,
+    ) -> (AtlasId, TextureAtlas) {
+        let sizes: Vec<_> = textures
+            .iter()
+            .map(|path| {
+                let dimensions = image::io::Reader::open(path)
+                    .map_err(image::ImageError::from)
+                    .and_then(|reader| reader.with_guessed_format()?.into_dimensions());
+
+                dimensions.unwrap_or_else(|error| {
+                    eprintln!("Warning: can't load texture {path:?}, its region is blank: {error}");
+                    (1, 1)
+                })
+            })
+            .collect();
+
+        self.atlas_count += 1;
+        (
+            AtlasId(self.atlas_count - 1),
+            TextureAtlas::pack(&sizes, ATLAS_PADDING).0,
+        )
+    }
+
+    fn create_texture_array(
+        &mut self,
+        _layers: &[Cow<'static, Path>],
+        _settings: &TextureSettings,
+    ) -> TextureArrayId {
+        self.texture_array_count += 1;
+        TextureArrayId(self.texture_array_count - 1)
+    }
+
+    fn create_mesh(&mut self, data: &MeshData) -> MeshId {
+        let id = MeshId(self.mesh_count);
+        self.mesh_count += 1;
+
+        self.custom_shapes
+            .insert(id, Rc::new(RefCell::new(data.get_triangle_mesh())));
+
+        id
+    }
+
+    fn update_mesh(&mut self, id: MeshId, data: &MeshData) {
+        if let Some(shape) = self.custom_shapes.get(&id) {
+            *shape.borrow_mut() = data.get_triangle_mesh();
+        }
+    }
+
+    fn update_mesh_vertices(&mut self, id: MeshId, data: &MeshData) {
+        if let Some(shape) = self.custom_shapes.get(&id) {
+            let mut shape = shape.borrow_mut();
+
+            if shape.positions.len() == data.positions.len() {
+                shape.set_positions(&data.positions);
+            } else {
+                *shape = data.get_triangle_mesh();
+            }
+        }
+    }
+
+    fn remove_mesh(&mut self, id: MeshId) {
+        self.custom_shapes.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Vec3A};
+
+    use super::*;
+    use crate::{AlphaMode, Texture, TextureDescriptor};
+
+    fn load(renderer: &mut HeadlessRenderer, mesh: Mesh) -> DrawComponent {
+        renderer.load(&DrawDescriptor {
+            mesh,
+            texture: TextureDescriptor::new(Texture::File(
+                Path::new("res/textures/container.jpg").into(),
+            )),
+            normal_map: None,
+            alpha_mode: AlphaMode::Opaque,
+        })
+    }
+
+    #[test]
+    fn missing_mesh_is_a_cube() {
+        let mut renderer = HeadlessRenderer::default();
+        let draw = load(&mut renderer, Mesh::Custom(MeshId(7)));
+        let cube = load(&mut renderer, Mesh::Cube);
+
+        assert_eq!(
+            draw.inner.get_shape().unwrap().bounds,
+            cube.inner.get_shape().unwrap().bounds
+        );
+    }
+
+    #[test]
+    fn vertex_updates_keep_indices() {
+        let mut renderer = HeadlessRenderer::default();
+        let mut data = MeshData {
+            positions: vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+            indices: Some(vec![0, 2, 1]),
+            ..Default::default()
+        };
+        let id = renderer.create_mesh(&data);
+        let draw = load(&mut renderer, Mesh::Custom(id));
+
+        data.positions[1] = Vec3::new(2.0, 0.0, 0.0);
+        renderer.update_mesh_vertices(id, &data);
+
+        let shape = draw.inner.get_shape().unwrap();
+        assert_eq!(shape.indices, vec![0, 2, 1]);
+        assert_eq!(shape.bounds.max, Vec3A::new(2.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,675 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+
+use super::{
+    mesh_data::{MeshData, MorphTarget},
+    ogl_renderer::MAX_MORPH_TARGETS,
+    AlphaMode, DrawDescriptor, Mesh, Texture, TextureDescriptor,
+};
+
+// What an importer knows about the material of a mesh
+#[derive(PartialEq, Clone, Debug)]
+pub struct ImportedMaterial {
+    pub base_color_texture: Option<PathBuf>,
+    pub normal_texture: Option<PathBuf>,
+    pub alpha_mode: AlphaMode,
+}
+
+impl ImportedMaterial {
+    // The fallback is used when the material has no base color texture
+    pub fn to_descriptor(&self, mesh: Mesh, fallback: TextureDescriptor) -> DrawDescriptor {
+        DrawDescriptor {
+            mesh,
+            texture: self.base_color_texture.as_ref().map_or(fallback, |path| {
+                TextureDescriptor::new(Texture::File(path.clone().into()))
+            }),
+            normal_map: self
+                .normal_texture
+                .as_ref()
+                .map(|path| TextureDescriptor::linear(Texture::File(path.clone().into()))),
+            alpha_mode: self.alpha_mode,
+        }
+    }
+}
+
+pub fn open_gltf(path: &Path) -> (gltf::Document, Vec<gltf::buffer::Data>) {
+    let gltf = gltf::Gltf::open(path).unwrap();
+    let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob).unwrap();
+
+    (gltf.document, buffers)
+}
+
+// The first node of the first scene with a mesh, children before their parents, and its
+// transform in the scene
+pub fn find_mesh_node(document: &gltf::Document) -> Option<(gltf::Node<'_>, Mat4)> {
+    fn find<'a>(node: gltf::Node<'a>, parent: Mat4) -> Option<(gltf::Node<'a>, Mat4)> {
+        let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+
+        node.children()
+            .find_map(|child| find(child, transform))
+            .or_else(|| node.mesh().map(|_| (node, transform)))
+    }
+
+    document
+        .scenes()
+        .next()?
+        .nodes()
+        .find_map(|node| find(node, Mat4::IDENTITY))
+}
+
+// Only the first primitive of the first mesh is used. Skinned meshes are left in the space
+// of their skeleton, everything else is moved by the transform of its node.
+pub fn load_gltf(path: &Path) -> MeshData {
+    let (document, buffers) = open_gltf(path);
+    let (node, transform) = find_mesh_node(&document).expect("No mesh in the glTF file");
+    let primitive = node.mesh().unwrap().primitives().next().unwrap();
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let vertex_count = primitive.get(&gltf::Semantic::Positions).unwrap().count();
+
+    let mut data = MeshData {
+        positions: reader.read_positions().unwrap().map(Vec3::from).collect(),
+        normals: reader
+            .read_normals()
+            .map(|normals| normals.map(Vec3::from).collect()),
+        tex_coords: reader
+            .read_tex_coords(0)
+            .map(|tex_coords| tex_coords.into_f32().map(Vec2::from).collect()),
+        tangents: reader
+            .read_tangents()
+            .map(|tangents| tangents.map(Vec4::from).collect()),
+        colors: reader
+            .read_colors(0)
+            .map(|colors| colors.into_rgba_f32().map(Vec4::from).collect()),
+        tex_coords_2: reader
+            .read_tex_coords(1)
+            .map(|tex_coords| tex_coords.into_f32().map(Vec2::from).collect()),
+        joints: reader
+            .read_joints(0)
+            .map(|joints| joints.into_u16().collect()),
+        weights: reader
+            .read_weights(0)
+            .map(|weights| weights.into_f32().map(Vec4::from).collect()),
+        indices: reader
+            .read_indices()
+            .map(|indices| indices.into_u32().collect()),
+        morph_targets: reader
+            .read_morph_targets()
+            .map(|(positions, normals, tangents)| MorphTarget {
+                positions: positions.map_or_else(
+                    || vec![Vec3::ZERO; vertex_count],
+                    |positions| positions.map(Vec3::from).collect(),
+                ),
+                normals: normals.map(|normals| normals.map(Vec3::from).collect()),
+                tangents: tangents.map(|tangents| tangents.map(Vec3::from).collect()),
+            })
+            .collect(),
+    };
+
+    // The renderer blends only as many targets as the shader has weights for
+    if data.morph_targets.len() > MAX_MORPH_TARGETS {
+        eprintln!(
+            "Warning: {path:?} has {} morph targets, only the first {MAX_MORPH_TARGETS} are used",
+            data.morph_targets.len()
+        );
+    }
+
+    if node.skin().is_none() {
+        let normal_mat = Mat3::from_mat4(transform).inverse().transpose();
+
+        for position in &mut data.positions {
+            *position = transform.transform_point3(*position);
+        }
+        for normal in data.normals.iter_mut().flatten() {
+            *normal = (normal_mat * *normal).normalize_or_zero();
+        }
+        for tangent in data.tangents.iter_mut().flatten() {
+            let xyz = transform
+                .transform_vector3(tangent.truncate())
+                .normalize_or_zero();
+            *tangent = xyz.extend(tangent.w);
+        }
+        // Offsets aren't moved by the translation, and aren't normalized
+        for target in &mut data.morph_targets {
+            for position in &mut target.positions {
+                *position = transform.transform_vector3(*position);
+            }
+            for normal in target.normals.iter_mut().flatten() {
+                *normal = normal_mat * *normal;
+            }
+            for tangent in target.tangents.iter_mut().flatten() {
+                *tangent = transform.transform_vector3(*tangent);
+            }
+        }
+    }
+
+    if data.normals.is_none() {
+        data.generate_normals();
+    }
+    if data.tangents.is_none() {
+        data.generate_tangents();
+    }
+
+    data
+}
+
+// The weights the mesh load_gltf loads starts with, zero for every morph target unless the
+// file says otherwise. MorphWeightsComponent::from_gltf uses them.
+pub fn load_gltf_morph_weights(path: &Path) -> Vec<f32> {
+    let (document, _) = open_gltf(path);
+    let Some((node, _)) = find_mesh_node(&document) else {
+        return Vec::new();
+    };
+    let mesh = node.mesh().unwrap();
+
+    match node.weights().or(mesh.weights()) {
+        Some(weights) => weights.to_vec(),
+        None => {
+            let targets = mesh
+                .primitives()
+                .next()
+                .map_or(0, |p| p.morph_targets().len());
+            vec![0.0; targets]
+        }
+    }
+}
+
+// The material of the primitive load_gltf uses, None when it has none. Images embedded in
+// the file are not supported and left out.
+pub fn load_gltf_material(path: &Path) -> Option<ImportedMaterial> {
+    let (document, _) = open_gltf(path);
+    let (node, _) = find_mesh_node(&document)?;
+    let material = node.mesh().unwrap().primitives().next()?.material();
+    material.index()?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let texture_path = |texture: gltf::Texture| match texture.source().source() {
+        gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+            Some(directory.join(uri))
+        }
+        _ => {
+            eprintln!("Warning: {path:?} has an embedded image, which is not supported");
+            None
+        }
+    };
+
+    Some(ImportedMaterial {
+        base_color_texture: material
+            .pbr_metallic_roughness()
+            .base_color_texture()
+            .and_then(|info| texture_path(info.texture())),
+        normal_texture: material
+            .normal_texture()
+            .and_then(|normal| texture_path(normal.texture())),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => {
+                AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5))
+            }
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+    })
+}
+
+fn obj_options() -> tobj::LoadOptions {
+    tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    }
+}
+
+// All objects in the file are merged into one mesh, load_obj_parts keeps materials apart
+pub fn load_obj(path: &Path) -> MeshData {
+    let (models, _) = tobj::load_obj(path, &obj_options()).unwrap();
+
+    merge_obj_models(&models)
+}
+
+// One mesh per material, objects without one share a mesh with no material. Texture paths
+// are relative to the OBJ file.
+pub fn load_obj_parts(path: &Path) -> Vec<(MeshData, Option<ImportedMaterial>)> {
+    let (models, materials) = tobj::load_obj(path, &obj_options()).unwrap();
+    let materials = materials.unwrap_or_default();
+
+    let mut parts: BTreeMap<Option<usize>, Vec<tobj::Model>> = BTreeMap::new();
+    for model in models {
+        parts.entry(model.mesh.material_id).or_default().push(model);
+    }
+
+    parts
+        .into_iter()
+        .map(|(material_id, models)| {
+            let material = material_id
+                .and_then(|id| materials.get(id))
+                .map(|material| obj_material(path, material));
+
+            (merge_obj_models(&models), material)
+        })
+        .collect()
+}
+
+// Normals are generated when any object is missing them, UVs and colors are only kept when
+// every object has them
+fn merge_obj_models(models: &[tobj::Model]) -> MeshData {
+    let all = |has: fn(&tobj::Mesh) -> bool| models.iter().all(|model| has(&model.mesh));
+    let has_normals = all(|mesh| !mesh.normals.is_empty());
+    let has_tex_coords = all(|mesh| !mesh.texcoords.is_empty());
+    let has_colors = all(|mesh| !mesh.vertex_color.is_empty());
+
+    let mut data = MeshData {
+        normals: has_normals.then(Vec::new),
+        tex_coords: has_tex_coords.then(Vec::new),
+        colors: has_colors.then(Vec::new),
+        indices: Some(Vec::new()),
+        ..Default::default()
+    };
+
+    for model in models {
+        let mesh = &model.mesh;
+        let start = data.positions.len() as u32;
+
+        data.positions
+            .extend(mesh.positions.chunks_exact(3).map(Vec3::from_slice));
+        if let Some(normals) = &mut data.normals {
+            normals.extend(mesh.normals.chunks_exact(3).map(Vec3::from_slice));
+        }
+        if let Some(tex_coords) = &mut data.tex_coords {
+            tex_coords.extend(mesh.texcoords.chunks_exact(2).map(Vec2::from_slice));
+        }
+        if let Some(colors) = &mut data.colors {
+            colors.extend(
+                mesh.vertex_color
+                    .chunks_exact(3)
+                    .map(|color| Vec3::from_slice(color).extend(1.0)),
+            );
+        }
+
+        data.indices
+            .as_mut()
+            .unwrap()
+            .extend(mesh.indices.iter().map(|i| start + i));
+    }
+
+    if !has_normals {
+        data.generate_normals();
+    }
+    data.generate_tangents();
+
+    data
+}
+
+fn obj_material(path: &Path, material: &tobj::Material) -> ImportedMaterial {
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let dissolve = material.dissolve.unwrap_or(1.0);
+    // A separate dissolve texture is usually a cutout mask
+    let alpha_mode = if material.dissolve_texture.is_some() {
+        AlphaMode::Mask(0.5)
+    } else if dissolve < 1.0 {
+        AlphaMode::Blend
+    } else {
+        AlphaMode::Opaque
+    };
+
+    ImportedMaterial {
+        base_color_texture: material
+            .diffuse_texture
+            .as_ref()
+            .map(|texture| directory.join(texture)),
+        // Exporters put normal maps in either map_Bump or the less common norm
+        normal_texture: material
+            .normal_texture
+            .as_ref()
+            .or(material.unknown_param.get("norm"))
+            .map(|texture| directory.join(texture)),
+        alpha_mode,
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Self {
+        match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => panic!("Unknown PLY type {name}"),
+        }
+    }
+
+    fn get_size(&self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+struct PlyProperty {
+    name: String,
+    kind: PlyType,
+    // The type of the item count, for list properties
+    list: Option<PlyType>,
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+struct PlyValues<'a> {
+    format: PlyFormat,
+    data: &'a [u8],
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl PlyValues<'_> {
+    fn next(&mut self, kind: PlyType) -> f64 {
+        if self.format == PlyFormat::Ascii {
+            return self.tokens.next().unwrap().parse().unwrap();
+        }
+
+        let (bytes, rest) = self.data.split_at(kind.get_size());
+        self.data = rest;
+
+        macro_rules! read {
+            ($type:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                match self.format {
+                    PlyFormat::BinaryBigEndian => <$type>::from_be_bytes(bytes) as f64,
+                    _ => <$type>::from_le_bytes(bytes) as f64,
+                }
+            }};
+        }
+
+        match kind {
+            PlyType::I8 => read!(i8),
+            PlyType::U8 => read!(u8),
+            PlyType::I16 => read!(i16),
+            PlyType::U16 => read!(u16),
+            PlyType::I32 => read!(i32),
+            PlyType::U32 => read!(u32),
+            PlyType::F32 => read!(f32),
+            PlyType::F64 => read!(f64),
+        }
+    }
+}
+
+// ASCII and binary PLY files with a vertex and a face element, with optional normals, UVs
+// and colors. Faces are triangulated as fans, other elements are skipped.
+pub fn load_ply(path: &Path) -> MeshData {
+    let bytes = fs::read(path).unwrap();
+
+    let header_end = bytes
+        .windows(10)
+        .position(|window| window == b"end_header")
+        .expect("Not a PLY file");
+    let data_start = header_end
+        + bytes[header_end..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .unwrap()
+        + 1;
+    let header = std::str::from_utf8(&bytes[..header_end]).unwrap();
+
+    let mut format = PlyFormat::Ascii;
+    let mut elements: Vec<PlyElement> = Vec::new();
+
+    for line in header.lines() {
+        let words: Vec<_> = line.split_ascii_whitespace().collect();
+
+        match words.as_slice() {
+            ["format", name, _] => {
+                format = match *name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => panic!("Unknown PLY format {name}"),
+                }
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().unwrap(),
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, kind, name] => {
+                elements.last_mut().unwrap().properties.push(PlyProperty {
+                    name: name.to_string(),
+                    kind: PlyType::parse(kind),
+                    list: Some(PlyType::parse(count)),
+                })
+            }
+            ["property", kind, name] => elements.last_mut().unwrap().properties.push(PlyProperty {
+                name: name.to_string(),
+                kind: PlyType::parse(kind),
+                list: None,
+            }),
+            _ => (),
+        }
+    }
+
+    let data = &bytes[data_start..];
+    let mut values = PlyValues {
+        format,
+        data,
+        tokens: match format {
+            PlyFormat::Ascii => std::str::from_utf8(data).unwrap().split_ascii_whitespace(),
+            _ => "".split_ascii_whitespace(),
+        },
+    };
+
+    let mut data = MeshData {
+        indices: Some(Vec::new()),
+        ..Default::default()
+    };
+
+    for element in &elements {
+        let index_of = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|property| names.contains(&property.name.as_str()))
+        };
+        let position = [index_of(&["x"]), index_of(&["y"]), index_of(&["z"])];
+        let normal = [index_of(&["nx"]), index_of(&["ny"]), index_of(&["nz"])];
+        let tex_coords = [
+            index_of(&["s", "u", "texture_u", "texture_s"]),
+            index_of(&["t", "v", "texture_v", "texture_t"]),
+        ];
+        let color = [
+            index_of(&["red", "r"]),
+            index_of(&["green", "g"]),
+            index_of(&["blue", "b"]),
+            index_of(&["alpha", "a"]),
+        ];
+        let face_indices = index_of(&["vertex_indices", "vertex_index"]);
+
+        if element.name == "vertex" {
+            let has = |indices: &[Option<usize>]| indices.iter().all(Option::is_some);
+
+            data.normals = has(&normal).then(Vec::new);
+            data.tex_coords = has(&tex_coords).then(Vec::new);
+            data.colors = has(&color[..3]).then(Vec::new);
+        }
+
+        for _ in 0..element.count {
+            let mut scalars = vec![0.0; element.properties.len()];
+
+            for (i, property) in element.properties.iter().enumerate() {
+                match property.list {
+                    Some(count) => {
+                        let count = values.next(count) as usize;
+                        let items: Vec<_> = (0..count)
+                            .map(|_| values.next(property.kind) as u32)
+                            .collect();
+
+                        if element.name == "face" && Some(i) == face_indices {
+                            let indices = data.indices.as_mut().unwrap();
+                            for j in 1..items.len().saturating_sub(1) {
+                                indices.extend([items[0], items[j], items[j + 1]]);
+                            }
+                        }
+                    }
+                    None => {
+                        // Integer colors are normalized
+                        let scale = match property.kind {
+                            PlyType::U8 if color.contains(&Some(i)) => 1.0 / 255.0,
+                            PlyType::U16 if color.contains(&Some(i)) => 1.0 / 65535.0,
+                            _ => 1.0,
+                        };
+                        scalars[i] = values.next(property.kind) as f32 * scale;
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                let get = |i: Option<usize>| i.map_or(0.0, |i| scalars[i]);
+
+                data.positions.push(Vec3::from_array(position.map(get)));
+                if let Some(normals) = &mut data.normals {
+                    normals.push(Vec3::from_array(normal.map(get)));
+                }
+                if let Some(tex_coords_list) = &mut data.tex_coords {
+                    tex_coords_list.push(Vec2::from_array(tex_coords.map(get)));
+                }
+                if let Some(colors) = &mut data.colors {
+                    let alpha = color[3].map_or(1.0, |i| scalars[i]);
+                    colors.push(
+                        Vec3::from_array([color[0], color[1], color[2]].map(get)).extend(alpha),
+                    );
+                }
+            }
+        }
+    }
+
+    if data.normals.is_none() {
+        data.generate_normals();
+    }
+    data.generate_tangents();
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{headless_renderer::HeadlessRenderer, load_model};
+
+    #[test]
+    fn obj_parts_per_material() {
+        let directory = std::env::temp_dir().join("obj_parts_per_material");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("quads.mtl"),
+            "newmtl red\nmap_Kd red.png\nnewmtl glass\nd 0.5\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("quads.obj"),
+            "mtllib quads.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+             o left\nusemtl red\nf 1 2 3 4\n\
+             o right\nusemtl glass\nf 2 5 6 3\n\
+             o top\nusemtl red\nf 4 3 6\n",
+        )
+        .unwrap();
+        let path = directory.join("quads.obj");
+
+        let parts = load_obj_parts(&path);
+        assert_eq!(parts.len(), 2);
+
+        let (red, red_material) = &parts[0];
+        let red_material = red_material.as_ref().unwrap();
+        assert_eq!(red.indices.as_ref().unwrap().len(), 9);
+        assert_eq!(
+            red_material.base_color_texture,
+            Some(directory.join("red.png"))
+        );
+        assert_eq!(red_material.alpha_mode, AlphaMode::Opaque);
+
+        let (glass, glass_material) = &parts[1];
+        assert_eq!(glass.indices.as_ref().unwrap().len(), 6);
+        assert_eq!(
+            glass_material.as_ref().unwrap().alpha_mode,
+            AlphaMode::Blend
+        );
+
+        assert_eq!(load_obj(&path).indices.unwrap().len(), 15);
+
+        let fallback = TextureDescriptor::new(Texture::File(Path::new("missing.png").into()));
+        let draws = load_model(
+            &mut HeadlessRenderer::default(),
+            Mesh::Obj(path.into()),
+            fallback,
+        );
+        let shapes: Vec<_> = draws
+            .iter()
+            .map(|draw| draw.inner.get_shape().unwrap().indices.len())
+            .collect();
+        assert_eq!(shapes, [9, 6]);
+    }
+
+    #[test]
+    fn gltf_material() {
+        let material = load_gltf_material(Path::new("res/gltf/teapot.gltf")).unwrap();
+
+        assert_eq!(material.base_color_texture, None);
+        assert_eq!(material.normal_texture, None);
+        assert_eq!(material.alpha_mode, AlphaMode::Opaque);
+    }
+
+    #[test]
+    fn gltf_default_morph_weights() {
+        let directory = std::env::temp_dir().join("gltf_default_morph_weights");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("morph.gltf");
+        // One triangle with two targets that reuse its positions
+        let gltf = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 36, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"}],
+            "bufferViews": [{"buffer": 0, "byteLength": 36}],
+            "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]}],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "targets": [{"POSITION": 0}, {"POSITION": 0}]}], "weights": WEIGHTS}],
+            "nodes": [{"mesh": 0}],
+            "scenes": [{"nodes": [0]}]
+        }"#;
+
+        fs::write(&path, gltf.replace("WEIGHTS", "[0.25, 0.5]")).unwrap();
+        assert_eq!(load_gltf_morph_weights(&path), [0.25, 0.5]);
+
+        fs::write(&path, gltf.replace(r#", "weights": WEIGHTS"#, "")).unwrap();
+        assert_eq!(load_gltf_morph_weights(&path), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,338 @@
+use glam::{Vec2, Vec3, Vec3A, Vec4};
+
+use crate::geometry::TriangleMesh;
+
+// The attributes a mesh can have, and the names shaders use for them. Attributes a shader
+// uses but a mesh doesn't have are filled with a default value.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum VertexAttribute {
+    Position,
+    // Defaults to +Z
+    Normal,
+    TexCoords,
+    // xyz is the tangent, w the sign of the bitangent. Defaults to +X.
+    Tangent,
+    // Defaults to white
+    Color,
+    TexCoords2,
+    // Joints and weights always come together. Default to full weight on joint 0.
+    Skin,
+}
+
+impl VertexAttribute {
+    pub fn get_names(&self) -> &'static [&'static str] {
+        match self {
+            Self::Position => &["position"],
+            Self::Normal => &["normal"],
+            Self::TexCoords => &["tex_coords"],
+            Self::Tangent => &["tangent"],
+            Self::Color => &["color"],
+            Self::TexCoords2 => &["tex_coords_2"],
+            Self::Skin => &["joints", "weights"],
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Default, Debug)]
+pub struct MeshVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub tex_coords: Vec2,
+}
+
+impl MeshVertex {
+    pub fn new(
+        x: f32,
+        y: f32,
+        z: f32,
+        normal_x: f32,
+        normal_y: f32,
+        normal_z: f32,
+        u: f32,
+        v: f32,
+    ) -> Self {
+        Self {
+            position: Vec3::new(x, y, z),
+            normal: Vec3::new(normal_x, normal_y, normal_z),
+            tex_coords: Vec2::new(u, v),
+        }
+    }
+}
+
+// Offsets from the base mesh, added in proportion to the weight of the target. Every set
+// attribute has one value per position of the mesh.
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct MorphTarget {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub tangents: Option<Vec<Vec3>>,
+}
+
+// Mesh data on the CPU side, triangle lists only. Every attribute that is set must have
+// one value per position.
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub tex_coords: Option<Vec<Vec2>>,
+    pub tangents: Option<Vec<Vec4>>,
+    pub colors: Option<Vec<Vec4>>,
+    pub tex_coords_2: Option<Vec<Vec2>>,
+    pub joints: Option<Vec<[u16; 4]>>,
+    pub weights: Option<Vec<Vec4>>,
+    pub indices: Option<Vec<u32>>,
+    pub morph_targets: Vec<MorphTarget>,
+}
+
+impl MeshData {
+    pub fn new(vertices: Vec<MeshVertex>, indices: Option<Vec<u32>>) -> Self {
+        Self {
+            positions: vertices.iter().map(|vertex| vertex.position).collect(),
+            normals: Some(vertices.iter().map(|vertex| vertex.normal).collect()),
+            tex_coords: Some(vertices.iter().map(|vertex| vertex.tex_coords).collect()),
+            indices,
+            ..Default::default()
+        }
+    }
+
+    // The rest pose, for picking and collision
+    pub fn get_triangle_mesh(&self) -> TriangleMesh {
+        TriangleMesh::new(
+            self.positions.iter().map(|p| Vec3A::from(*p)).collect(),
+            self.indices.clone(),
+        )
+    }
+
+    pub fn has_attribute(&self, attribute: VertexAttribute) -> bool {
+        match attribute {
+            VertexAttribute::Position => true,
+            VertexAttribute::Normal => self.normals.is_some(),
+            VertexAttribute::TexCoords => self.tex_coords.is_some(),
+            VertexAttribute::Tangent => self.tangents.is_some(),
+            VertexAttribute::Color => self.colors.is_some(),
+            VertexAttribute::TexCoords2 => self.tex_coords_2.is_some(),
+            VertexAttribute::Skin => self.joints.is_some() && self.weights.is_some(),
+        }
+    }
+
+    // Smooth normals, weighted by the area of each face
+    pub fn generate_normals(&mut self) {
+        let indices = self
+            .indices
+            .clone()
+            .unwrap_or_else(|| (0..self.positions.len() as u32).collect());
+        let mut normals = vec![Vec3::ZERO; self.positions.len()];
+
+        for face in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.positions[face[i] as usize]);
+            let normal = (b - a).cross(c - a);
+
+            for i in face {
+                normals[*i as usize] += normal;
+            }
+        }
+
+        self.normals = Some(
+            normals
+                .into_iter()
+                .map(|normal| normal.normalize_or_zero())
+                .collect(),
+        );
+    }
+
+    // Needed for normal mapping, and only possible with normals and UVs. MikkTSpace gives
+    // each corner of a face its own tangent, so vertices whose corners disagree, like along
+    // UV seams and mirrored halves, are split.
+    pub fn generate_tangents(&mut self) {
+        let (Some(normals), Some(tex_coords)) = (&self.normals, &self.tex_coords) else {
+            return;
+        };
+
+        let corner_count = self.indices.as_ref().map_or(self.positions.len(), Vec::len);
+        let mut geometry = TangentGeometry {
+            positions: &self.positions,
+            normals,
+            tex_coords,
+            indices: self.indices.as_deref(),
+            tangents: vec![Vec4::ZERO; corner_count],
+        };
+        mikktspace::generate_tangents(&mut geometry);
+        let corner_tangents = geometry.tangents;
+
+        self.tangents = None;
+        let Some(mut indices) = self.indices.take() else {
+            self.tangents = Some(corner_tangents);
+            return;
+        };
+
+        // The vertex and its copies, each with a different tangent
+        let mut copies = vec![Vec::new(); self.positions.len()];
+        let mut tangents = vec![Vec4::ZERO; self.positions.len()];
+
+        for (index, tangent) in indices.iter_mut().zip(corner_tangents) {
+            let vertex_copies: &mut Vec<u32> = &mut copies[*index as usize];
+
+            if let Some(copy) = vertex_copies
+                .iter()
+                .find(|copy| tangents[**copy as usize].abs_diff_eq(tangent, 1e-5))
+            {
+                *index = *copy;
+                continue;
+            }
+
+            if !vertex_copies.is_empty() {
+                let copy = self.duplicate_vertex(*index as usize);
+                copies[*index as usize].push(copy);
+                tangents.push(tangent);
+                *index = copy;
+            } else {
+                vertex_copies.push(*index);
+                tangents[*index as usize] = tangent;
+            }
+        }
+
+        self.indices = Some(indices);
+        self.tangents = Some(tangents);
+    }
+
+    // Appends a copy of a vertex with all its attributes except the tangent, returning its
+    // index
+    fn duplicate_vertex(&mut self, index: usize) -> u32 {
+        fn push<T: Copy>(values: &mut Option<Vec<T>>, index: usize) {
+            if let Some(values) = values {
+                values.push(values[index]);
+            }
+        }
+
+        self.positions.push(self.positions[index]);
+        push(&mut self.normals, index);
+        push(&mut self.tex_coords, index);
+        push(&mut self.colors, index);
+        push(&mut self.tex_coords_2, index);
+        push(&mut self.joints, index);
+        push(&mut self.weights, index);
+
+        for target in &mut self.morph_targets {
+            target.positions.push(target.positions[index]);
+            push(&mut target.normals, index);
+            push(&mut target.tangents, index);
+        }
+
+        (self.positions.len() - 1) as u32
+    }
+}
+
+struct TangentGeometry<'a> {
+    positions: &'a [Vec3],
+    normals: &'a [Vec3],
+    tex_coords: &'a [Vec2],
+    indices: Option<&'a [u32]>,
+    // One for each corner of each face
+    tangents: Vec<Vec4>,
+}
+
+impl TangentGeometry<'_> {
+    fn index(&self, face: usize, vert: usize) -> usize {
+        match self.indices {
+            Some(indices) => indices[face * 3 + vert] as usize,
+            None => face * 3 + vert,
+        }
+    }
+}
+
+impl mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.map_or(self.positions.len(), |i| i.len()) / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[self.index(face, vert)].to_array()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[self.index(face, vert)].to_array()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.tex_coords[self.index(face, vert)].to_array()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = Vec4::from_array(tangent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quads(tex_coords: Vec<Vec2>) -> MeshData {
+        let mut data = MeshData {
+            positions: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(2.0, 1.0, 0.0),
+            ],
+            normals: Some(vec![Vec3::Z; 6]),
+            tex_coords: Some(tex_coords),
+            indices: Some(vec![0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2]),
+            ..Default::default()
+        };
+        data.generate_tangents();
+        data
+    }
+
+    #[test]
+    fn continuous_uvs_share_vertices() {
+        let data = quads(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.5, 0.0),
+            Vec2::new(0.5, 1.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+        ]);
+
+        assert_eq!(data.positions.len(), 6);
+        for tangent in data.tangents.unwrap() {
+            assert!(tangent.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, 1.0), 1e-5));
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_split_vertices() {
+        // The right quad mirrors the left one, so their shared edge needs both tangents
+        let data = quads(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 1.0),
+        ]);
+
+        assert_eq!(data.positions.len(), 8);
+        assert_eq!(data.normals.as_ref().unwrap().len(), 8);
+
+        let indices = data.indices.unwrap();
+        let tangents = data.tangents.unwrap();
+        for (corner, index) in indices.iter().enumerate() {
+            let x = if corner < 6 { 1.0 } else { -1.0 };
+            assert!(tangents[*index as usize]
+                .truncate()
+                .abs_diff_eq(Vec3::new(x, 0.0, 0.0), 1e-5));
+        }
+        assert_eq!(
+            data.positions[indices[6] as usize],
+            Vec3::new(1.0, 0.0, 0.0)
+        );
+        assert_ne!(indices[6], indices[1]);
+    }
+}
@@ -1,32 +1,316 @@
-use std::{any::Any, borrow::Cow, path::Path};
+use std::{any::Any, borrow::Cow, cell::Ref, path::Path};
 
 use brood::World;
 
 use crate::{
     components::{draw::DrawComponent, Registry},
+    geometry::TriangleMesh,
     resources::Resources,
 };
 
+use self::{atlas::TextureAtlas, mesh_data::MeshData};
+
+pub mod atlas;
+pub mod environment;
+pub mod headless_renderer;
+pub mod import;
+pub mod mesh_data;
+pub mod ogl_mesh;
+pub mod ogl_post_process;
 pub mod ogl_renderer;
+pub mod ogl_texture;
+pub mod shapes;
+pub mod texture_data;
 
 pub trait Renderer {
     fn render(&mut self, world: &mut World<Registry, Resources>);
     fn load(&mut self, descriptor: &DrawDescriptor) -> DrawComponent;
+    fn create_render_target(&mut self, width: u32, height: u32) -> RenderTargetId;
+    fn set_skybox(&mut self, skybox: Option<&Skybox>);
+    // Only the color space and mipmap settings matter here, sampling is set per draw
+    fn create_atlas(
+        &mut self,
+        textures: &[Cow<'static, Path>],
+        settings: &TextureSettings,
+    ) -> (AtlasId, TextureAtlas);
+    // Layers are resized to match the first one
+    fn create_texture_array(
+        &mut self,
+        layers: &[Cow<'static, Path>],
+        settings: &TextureSettings,
+    ) -> TextureArrayId;
+    // Registered meshes are drawn with Mesh::Custom
+    fn create_mesh(&mut self, data: &MeshData) -> MeshId;
+    // Writes into the existing buffers when the attributes, vertex count and indices stay the
+    // same, so it's cheap enough to do every frame. Draw components using the mesh see the update.
+    fn update_mesh(&mut self, id: MeshId, data: &MeshData);
+    // Like update_mesh, for when only the vertices changed and the indices and morph targets
+    // are the same, which skips comparing them
+    fn update_mesh_vertices(&mut self, id: MeshId, data: &MeshData);
+    // Components already using the mesh keep it alive
+    fn remove_mesh(&mut self, id: MeshId);
 }
 
 pub trait DrawData {
     fn as_any(&self) -> &dyn Any;
+    fn get_shape(&self) -> Option<Ref<'_, TriangleMesh>>;
 }
 
 pub struct DrawDescriptor {
     pub mesh: Mesh,
-    pub texture: Cow<'static, Path>,
+    pub texture: TextureDescriptor,
+    // Tangent space, OpenGL convention (green up)
+    pub normal_map: Option<TextureDescriptor>,
+    pub alpha_mode: AlphaMode,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum AlphaMode {
+    Opaque,
+    // Pixels with alpha below the cutoff are discarded, the rest are opaque
+    Mask(f32),
+    // Drawn after everything opaque, back to front, without writing depth
+    Blend,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
 pub enum Mesh {
     Triangle,
     Square,
     Cube,
     Gltf(Cow<'static, Path>),
+    // Wavefront OBJ, all objects merged into one mesh
+    Obj(Cow<'static, Path>),
+    Ply(Cow<'static, Path>),
+    Custom(MeshId),
+    // Generated shapes are centered on the origin, with Y as their axis
+    UvSphere {
+        radius: Dimension,
+        segments: u32,
+        rings: u32,
+    },
+    Icosphere {
+        radius: Dimension,
+        subdivisions: u32,
+    },
+    // Facing up, split into subdivisions + 1 quads along each side
+    Plane {
+        width: Dimension,
+        depth: Dimension,
+        subdivisions: u32,
+    },
+    Cylinder {
+        radius: Dimension,
+        height: Dimension,
+        segments: u32,
+    },
+    Cone {
+        radius: Dimension,
+        height: Dimension,
+        segments: u32,
+    },
+    // The height is of the cylindrical part, the caps add the radius on each end
+    Capsule {
+        radius: Dimension,
+        height: Dimension,
+        segments: u32,
+        rings: u32,
+    },
+    Torus {
+        radius: Dimension,
+        tube_radius: Dimension,
+        segments: u32,
+        sides: u32,
+    },
+}
+
+impl Mesh {
+    // Renderers keep the data of custom meshes themselves
+    pub fn load_data(&self) -> MeshData {
+        match self {
+            Self::Gltf(path) => import::load_gltf(path),
+            Self::Obj(path) => import::load_obj(path),
+            Self::Ply(path) => import::load_ply(path),
+            Self::Custom(id) => panic!("Custom mesh {id:?} has no data of its own"),
+            _ => shapes::generate(self).unwrap(),
+        }
+    }
+}
+
+// Loads a model file with the materials it comes with, one draw component per part. OBJ files
+// have a part per material, registered as custom meshes, glTF and PLY files a single one. The
+// fallback texture is used where there is no base color texture.
+pub fn load_model(
+    renderer: &mut dyn Renderer,
+    mesh: Mesh,
+    fallback: TextureDescriptor,
+) -> Vec<DrawComponent> {
+    let descriptor = |mesh, material: Option<import::ImportedMaterial>| match material {
+        Some(material) => material.to_descriptor(mesh, fallback.clone()),
+        None => DrawDescriptor {
+            mesh,
+            texture: fallback.clone(),
+            normal_map: None,
+            alpha_mode: AlphaMode::Opaque,
+        },
+    };
+
+    let descriptors: Vec<_> = match &mesh {
+        Mesh::Obj(path) => import::load_obj_parts(path)
+            .into_iter()
+            .map(|(data, material)| descriptor(Mesh::Custom(renderer.create_mesh(&data)), material))
+            .collect(),
+        Mesh::Gltf(path) => {
+            let material = import::load_gltf_material(path);
+            vec![descriptor(mesh, material)]
+        }
+        _ => vec![descriptor(mesh, None)],
+    };
+
+    descriptors
+        .iter()
+        .map(|descriptor| renderer.load(descriptor))
+        .collect()
+}
+
+// A float that can be part of a mesh key, compared by its bits
+#[derive(Clone, Copy, Debug)]
+pub struct Dimension(pub f32);
+
+impl PartialEq for Dimension {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Dimension {}
+
+impl PartialOrd for Dimension {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dimension {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl std::hash::Hash for Dimension {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl From<f32> for Dimension {
+    fn from(value: f32) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum Texture {
+    File(Cow<'static, Path>),
+    RenderTarget(RenderTargetId),
+    // With a region the mesh UVs are remapped into it, which breaks repeating wrap modes.
+    // Without one the mesh UVs are expected to already be in atlas space.
+    Atlas {
+        atlas: AtlasId,
+        region: Option<usize>,
+    },
+    ArrayLayer {
+        array: TextureArrayId,
+        layer: u32,
+    },
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct RenderTargetId(pub u32);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct MeshId(pub u32);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct AtlasId(pub u32);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct TextureArrayId(pub u32);
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct TextureDescriptor {
+    pub texture: Texture,
+    pub settings: TextureSettings,
+}
+
+impl TextureDescriptor {
+    // For color textures
+    pub fn new(texture: Texture) -> Self {
+        Self {
+            texture,
+            settings: TextureSettings::default(),
+        }
+    }
+
+    // For data such as normal maps that must not be gamma decoded
+    pub fn linear(texture: Texture) -> Self {
+        Self {
+            texture,
+            settings: TextureSettings {
+                color_space: ColorSpace::Linear,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct TextureSettings {
+    pub color_space: ColorSpace,
+    pub wrap: WrapMode,
+    pub min_filter: FilterMode,
+    pub mag_filter: FilterMode,
+    // Mipmaps are only generated when this is set
+    pub mipmap_filter: Option<FilterMode>,
+    // 1 disables anisotropic filtering
+    pub anisotropy: u16,
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            color_space: ColorSpace::Srgb,
+            wrap: WrapMode::Repeat,
+            min_filter: FilterMode::Linear,
+            mag_filter: FilterMode::Linear,
+            mipmap_filter: Some(FilterMode::Linear),
+            anisotropy: 8,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    Clamp,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum FilterMode {
+    Nearest,
+    Linear,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub enum Skybox {
+    // Right, left, top, bottom, front, back
+    Faces([Cow<'static, Path>; 6]),
+    Equirectangular(Cow<'static, Path>),
 }
@@ -0,0 +1,424 @@
+use std::borrow::Cow;
+
+use glam::Vec3;
+use glium::{
+    glutin::surface::WindowSurface,
+    implement_vertex,
+    index::{IndexBufferAny, PrimitiveType},
+    texture::{ClientFormat, MipmapsOption, RawImage2d, UncompressedFloatFormat},
+    vertex::VerticesSource,
+    Display, IndexBuffer, Texture2d, VertexBuffer,
+};
+
+use crate::geometry::TriangleMesh;
+
+use super::mesh_data::{MeshData, MorphTarget, VertexAttribute};
+
+pub const MORPH_TEXTURE_WIDTH: usize = 4096;
+
+#[derive(Copy, Clone)]
+pub struct PositionAttribute {
+    position: [f32; 3],
+}
+implement_vertex!(PositionAttribute, position);
+
+#[derive(Copy, Clone)]
+pub struct NormalAttribute {
+    normal: [f32; 3],
+}
+implement_vertex!(NormalAttribute, normal);
+
+#[derive(Copy, Clone)]
+pub struct TexCoordsAttribute {
+    tex_coords: [f32; 2],
+}
+implement_vertex!(TexCoordsAttribute, tex_coords);
+
+#[derive(Copy, Clone)]
+pub struct TangentAttribute {
+    tangent: [f32; 4],
+}
+implement_vertex!(TangentAttribute, tangent);
+
+#[derive(Copy, Clone)]
+pub struct ColorAttribute {
+    color: [f32; 4],
+}
+implement_vertex!(ColorAttribute, color);
+
+#[derive(Copy, Clone)]
+pub struct TexCoords2Attribute {
+    tex_coords_2: [f32; 2],
+}
+implement_vertex!(TexCoords2Attribute, tex_coords_2);
+
+#[derive(Copy, Clone)]
+pub struct SkinAttribute {
+    joints: [u16; 4],
+    weights: [f32; 4],
+}
+implement_vertex!(SkinAttribute, joints, weights);
+
+// Single vertex buffers bound per instance for attributes a mesh doesn't have, so every
+// vertex reads the same value
+pub struct VertexDefaults {
+    normal: VertexBuffer<NormalAttribute>,
+    tex_coords: VertexBuffer<TexCoordsAttribute>,
+    tangent: VertexBuffer<TangentAttribute>,
+    color: VertexBuffer<ColorAttribute>,
+    tex_coords_2: VertexBuffer<TexCoords2Attribute>,
+    skin: VertexBuffer<SkinAttribute>,
+}
+
+impl VertexDefaults {
+    pub fn new(display: &Display<WindowSurface>) -> Self {
+        Self {
+            normal: VertexBuffer::new(
+                display,
+                &[NormalAttribute {
+                    normal: [0.0, 0.0, 1.0],
+                }],
+            )
+            .unwrap(),
+            tex_coords: VertexBuffer::new(
+                display,
+                &[TexCoordsAttribute {
+                    tex_coords: [0.0; 2],
+                }],
+            )
+            .unwrap(),
+            tangent: VertexBuffer::new(
+                display,
+                &[TangentAttribute {
+                    tangent: [1.0, 0.0, 0.0, 1.0],
+                }],
+            )
+            .unwrap(),
+            color: VertexBuffer::new(display, &[ColorAttribute { color: [1.0; 4] }]).unwrap(),
+            tex_coords_2: VertexBuffer::new(
+                display,
+                &[TexCoords2Attribute {
+                    tex_coords_2: [0.0; 2],
+                }],
+            )
+            .unwrap(),
+            skin: VertexBuffer::new(
+                display,
+                &[SkinAttribute {
+                    joints: [0; 4],
+                    weights: [1.0, 0.0, 0.0, 0.0],
+                }],
+            )
+            .unwrap(),
+        }
+    }
+}
+
+// One buffer per attribute, so meshes only store what they have
+pub struct OglMesh {
+    pub positions: VertexBuffer<PositionAttribute>,
+    pub normals: Option<VertexBuffer<NormalAttribute>>,
+    pub tex_coords: Option<VertexBuffer<TexCoordsAttribute>>,
+    pub tangents: Option<VertexBuffer<TangentAttribute>>,
+    pub colors: Option<VertexBuffer<ColorAttribute>>,
+    pub tex_coords_2: Option<VertexBuffer<TexCoords2Attribute>>,
+    pub skin: Option<VertexBuffer<SkinAttribute>>,
+    // 16 bit when there are few enough vertices
+    pub indices: Option<IndexBufferAny>,
+    // Position, normal and tangent offsets of every vertex of every target, one texel each,
+    // wrapped into rows of at most MORPH_TEXTURE_WIDTH
+    pub morph_targets: Option<Texture2d>,
+    pub morph_target_count: usize,
+    // Kept to tell whether the texture needs rebuilding on updates
+    morph_target_data: Vec<MorphTarget>,
+    pub shape: TriangleMesh,
+}
+
+impl OglMesh {
+    pub fn new(display: &Display<WindowSurface>, data: &MeshData) -> Self {
+        let streams = Streams::new(data);
+
+        Self {
+            positions: VertexBuffer::new(display, &streams.positions).unwrap(),
+            normals: streams
+                .normals
+                .map(|normals| VertexBuffer::new(display, &normals).unwrap()),
+            tex_coords: streams
+                .tex_coords
+                .map(|tex_coords| VertexBuffer::new(display, &tex_coords).unwrap()),
+            tangents: streams
+                .tangents
+                .map(|tangents| VertexBuffer::new(display, &tangents).unwrap()),
+            colors: streams
+                .colors
+                .map(|colors| VertexBuffer::new(display, &colors).unwrap()),
+            tex_coords_2: streams
+                .tex_coords_2
+                .map(|tex_coords_2| VertexBuffer::new(display, &tex_coords_2).unwrap()),
+            skin: streams
+                .skin
+                .map(|skin| VertexBuffer::new(display, &skin).unwrap()),
+            indices: data.indices.as_ref().map(|indices| {
+                if data.positions.len() <= u16::MAX as usize + 1 {
+                    let indices: Vec<_> = indices.iter().map(|i| *i as u16).collect();
+                    IndexBuffer::new(display, PrimitiveType::TrianglesList, &indices)
+                        .unwrap()
+                        .into()
+                } else {
+                    IndexBuffer::new(display, PrimitiveType::TrianglesList, indices)
+                        .unwrap()
+                        .into()
+                }
+            }),
+            morph_targets: morph_texture(display, data),
+            morph_target_count: data.morph_targets.len(),
+            morph_target_data: data.morph_targets.clone(),
+            shape: data.get_triangle_mesh(),
+        }
+    }
+
+    pub fn has_attribute(&self, attribute: VertexAttribute) -> bool {
+        match attribute {
+            VertexAttribute::Position => true,
+            VertexAttribute::Normal => self.normals.is_some(),
+            VertexAttribute::TexCoords => self.tex_coords.is_some(),
+            VertexAttribute::Tangent => self.tangents.is_some(),
+            VertexAttribute::Color => self.colors.is_some(),
+            VertexAttribute::TexCoords2 => self.tex_coords_2.is_some(),
+            VertexAttribute::Skin => self.skin.is_some(),
+        }
+    }
+
+    // Writes into the existing buffers when the attributes, vertex count and indices are the
+    // same, otherwise the buffers are recreated. The morph texture is only rebuilt when the
+    // targets changed.
+    pub fn update(&mut self, display: &Display<WindowSurface>, data: &MeshData) {
+        let same_indices = self.indices.is_some() == data.indices.is_some()
+            && data
+                .indices
+                .as_ref()
+                .map_or(true, |indices| *indices == self.shape.indices);
+
+        if !self.same_attributes(data) || !same_indices {
+            *self = Self::new(display, data);
+            return;
+        }
+
+        self.write_vertices(data);
+
+        if data.morph_targets != self.morph_target_data {
+            self.morph_targets = morph_texture(display, data);
+            self.morph_target_count = data.morph_targets.len();
+            self.morph_target_data = data.morph_targets.clone();
+        }
+    }
+
+    // For when the caller knows the indices and morph targets are unchanged, so they aren't
+    // compared
+    pub fn update_vertices(&mut self, display: &Display<WindowSurface>, data: &MeshData) {
+        if !self.same_attributes(data) {
+            *self = Self::new(display, data);
+            return;
+        }
+
+        self.write_vertices(data);
+    }
+
+    fn same_attributes(&self, data: &MeshData) -> bool {
+        let attributes = [
+            VertexAttribute::Normal,
+            VertexAttribute::TexCoords,
+            VertexAttribute::Tangent,
+            VertexAttribute::Color,
+            VertexAttribute::TexCoords2,
+            VertexAttribute::Skin,
+        ];
+
+        self.positions.len() == data.positions.len()
+            && attributes
+                .iter()
+                .all(|attribute| self.has_attribute(*attribute) == data.has_attribute(*attribute))
+    }
+
+    fn write_vertices(&mut self, data: &MeshData) {
+        let streams = Streams::new(data);
+
+        self.positions.write(&streams.positions);
+        write(&self.normals, streams.normals);
+        write(&self.tex_coords, streams.tex_coords);
+        write(&self.tangents, streams.tangents);
+        write(&self.colors, streams.colors);
+        write(&self.tex_coords_2, streams.tex_coords_2);
+        write(&self.skin, streams.skin);
+
+        self.shape.set_positions(&data.positions);
+    }
+
+    // Every attribute is bound, falling back to the defaults, so any shader can be used
+    // with any mesh
+    pub fn get_vertices<'a>(
+        &'a self,
+        defaults: &'a VertexDefaults,
+    ) -> (
+        VerticesSource<'a>,
+        VerticesSource<'a>,
+        VerticesSource<'a>,
+        VerticesSource<'a>,
+        VerticesSource<'a>,
+        VerticesSource<'a>,
+        VerticesSource<'a>,
+    ) {
+        (
+            (&self.positions).into(),
+            source(&self.normals, &defaults.normal),
+            source(&self.tex_coords, &defaults.tex_coords),
+            source(&self.tangents, &defaults.tangent),
+            source(&self.colors, &defaults.color),
+            source(&self.tex_coords_2, &defaults.tex_coords_2),
+            source(&self.skin, &defaults.skin),
+        )
+    }
+}
+
+// The attributes of mesh data, converted to what gets uploaded
+struct Streams {
+    positions: Vec<PositionAttribute>,
+    normals: Option<Vec<NormalAttribute>>,
+    tex_coords: Option<Vec<TexCoordsAttribute>>,
+    tangents: Option<Vec<TangentAttribute>>,
+    colors: Option<Vec<ColorAttribute>>,
+    tex_coords_2: Option<Vec<TexCoords2Attribute>>,
+    skin: Option<Vec<SkinAttribute>>,
+}
+
+impl Streams {
+    fn new(data: &MeshData) -> Self {
+        Self {
+            positions: data
+                .positions
+                .iter()
+                .map(|position| PositionAttribute {
+                    position: position.to_array(),
+                })
+                .collect(),
+            normals: data.normals.as_ref().map(|normals| {
+                normals
+                    .iter()
+                    .map(|normal| NormalAttribute {
+                        normal: normal.to_array(),
+                    })
+                    .collect()
+            }),
+            tex_coords: data.tex_coords.as_ref().map(|tex_coords| {
+                tex_coords
+                    .iter()
+                    .map(|tex_coords| TexCoordsAttribute {
+                        tex_coords: tex_coords.to_array(),
+                    })
+                    .collect()
+            }),
+            tangents: data.tangents.as_ref().map(|tangents| {
+                tangents
+                    .iter()
+                    .map(|tangent| TangentAttribute {
+                        tangent: tangent.to_array(),
+                    })
+                    .collect()
+            }),
+            colors: data.colors.as_ref().map(|colors| {
+                colors
+                    .iter()
+                    .map(|color| ColorAttribute {
+                        color: color.to_array(),
+                    })
+                    .collect()
+            }),
+            tex_coords_2: data.tex_coords_2.as_ref().map(|tex_coords_2| {
+                tex_coords_2
+                    .iter()
+                    .map(|tex_coords_2| TexCoords2Attribute {
+                        tex_coords_2: tex_coords_2.to_array(),
+                    })
+                    .collect()
+            }),
+            skin: data
+                .joints
+                .as_ref()
+                .zip(data.weights.as_ref())
+                .map(|(joints, weights)| {
+                    joints
+                        .iter()
+                        .zip(weights)
+                        .map(|(joints, weights)| SkinAttribute {
+                            joints: *joints,
+                            weights: weights.to_array(),
+                        })
+                        .collect()
+                }),
+        }
+    }
+}
+
+fn morph_texture(display: &Display<WindowSurface>, data: &MeshData) -> Option<Texture2d> {
+    if data.morph_targets.is_empty() {
+        return None;
+    }
+
+    let mut texels: Vec<f32> = data
+        .morph_targets
+        .iter()
+        .flat_map(|target| {
+            let get = |offsets: &Option<Vec<Vec3>>, i: usize| {
+                offsets.as_ref().map_or(Vec3::ZERO, |offsets| offsets[i])
+            };
+
+            (0..data.positions.len()).flat_map(move |i| {
+                [
+                    target.positions[i],
+                    get(&target.normals, i),
+                    get(&target.tangents, i),
+                ]
+                .into_iter()
+                .flat_map(|offset| offset.extend(0.0).to_array())
+            })
+        })
+        .collect();
+
+    let count = texels.len() / 4;
+    let width = count.clamp(1, MORPH_TEXTURE_WIDTH);
+    let height = count.div_ceil(width);
+    texels.resize(width * height * 4, 0.0);
+
+    let image = RawImage2d {
+        data: Cow::Owned(texels),
+        width: width as u32,
+        height: height as u32,
+        format: ClientFormat::F32F32F32F32,
+    };
+
+    Some(
+        Texture2d::with_format(
+            display,
+            image,
+            UncompressedFloatFormat::F32F32F32F32,
+            MipmapsOption::NoMipmap,
+        )
+        .unwrap(),
+    )
+}
+
+fn write<T: Copy + Send + 'static>(buffer: &Option<VertexBuffer<T>>, data: Option<Vec<T>>) {
+    if let (Some(buffer), Some(data)) = (buffer, data) {
+        buffer.write(&data);
+    }
+}
+
+fn source<'a, T: Copy>(
+    buffer: &'a Option<VertexBuffer<T>>,
+    default: &'a VertexBuffer<T>,
+) -> VerticesSource<'a> {
+    match buffer {
+        Some(buffer) => buffer.into(),
+        None => default.per_instance().unwrap().into(),
+    }
+}
@@ -0,0 +1,340 @@
+use std::{borrow::Cow, collections::HashMap, fs, path::Path};
+
+use glium::{
+    framebuffer::{DepthRenderBuffer, RenderBuffer, SimpleFrameBuffer},
+    glutin::surface::WindowSurface,
+    index::{NoIndices, PrimitiveType},
+    texture::{DepthFormat, MipmapsOption, RawImage2d, UncompressedFloatFormat},
+    uniform,
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler, SamplerWrapFunction, Uniforms},
+    vertex::EmptyVertexAttributes,
+    BlitTarget, Display, Program, Surface, Texture2d,
+};
+
+use crate::resources::{
+    anti_aliasing::AntiAliasingResource,
+    post_process::{PostProcessResource, ToneMapping},
+};
+
+// Everything a camera target needs to be rendered in HDR and post processed
+pub struct HdrBuffers {
+    color: Texture2d,
+    depth: DepthRenderBuffer,
+    samples: Option<u32>,
+    multisampled: Option<(RenderBuffer, DepthRenderBuffer)>,
+    swap: Texture2d,
+    bloom: [Texture2d; 2],
+    ldr: Texture2d,
+}
+
+impl HdrBuffers {
+    fn new(
+        display: &Display<WindowSurface>,
+        (width, height): (u32, u32),
+        samples: Option<u32>,
+    ) -> Self {
+        let bloom_size = ((width / 2).max(1), (height / 2).max(1));
+
+        Self {
+            color: hdr_texture(display, (width, height)),
+            depth: DepthRenderBuffer::new(display, DepthFormat::I24, width, height).unwrap(),
+            samples,
+            multisampled: samples.map(|samples| {
+                (
+                    RenderBuffer::new_multisample(
+                        display,
+                        UncompressedFloatFormat::F16F16F16F16,
+                        width,
+                        height,
+                        samples,
+                    )
+                    .unwrap(),
+                    DepthRenderBuffer::new_multisample(
+                        display,
+                        DepthFormat::I24,
+                        width,
+                        height,
+                        samples,
+                    )
+                    .unwrap(),
+                )
+            }),
+            swap: hdr_texture(display, (width, height)),
+            bloom: [
+                hdr_texture(display, bloom_size),
+                hdr_texture(display, bloom_size),
+            ],
+            ldr: Texture2d::empty_with_format(
+                display,
+                UncompressedFloatFormat::U8U8U8U8,
+                MipmapsOption::NoMipmap,
+                width,
+                height,
+            )
+            .unwrap(),
+        }
+    }
+
+    // The framebuffer cameras draw into, multisampled when MSAA is on
+    pub fn get_framebuffer(&self, display: &Display<WindowSurface>) -> SimpleFrameBuffer<'_> {
+        match &self.multisampled {
+            Some((color, depth)) => SimpleFrameBuffer::with_depth_buffer(display, color, depth),
+            None => SimpleFrameBuffer::with_depth_buffer(display, &self.color, &self.depth),
+        }
+        .unwrap()
+    }
+
+    // Resolves the multisampled framebuffer into the texture post processing reads from
+    pub fn resolve(&self, display: &Display<WindowSurface>, framebuffer: &SimpleFrameBuffer) {
+        if self.multisampled.is_none() {
+            return;
+        }
+
+        let (width, height) = self.color.dimensions();
+        framebuffer.blit_whole_color_to(
+            &SimpleFrameBuffer::new(display, &self.color).unwrap(),
+            &BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: width as i32,
+                height: height as i32,
+            },
+            MagnifySamplerFilter::Nearest,
+        );
+    }
+}
+
+pub struct OglPostProcess {
+    vertex_source: String,
+    extract_program: Program,
+    blur_program: Program,
+    composite_program: Program,
+    fxaa_program: Program,
+    pass_programs: HashMap<Cow<'static, Path>, Program>,
+    luts: HashMap<Cow<'static, Path>, Texture2d>,
+    black: Texture2d,
+    buffers: HashMap<(u32, u32), HdrBuffers>,
+}
+
+impl OglPostProcess {
+    pub fn new(display: &Display<WindowSurface>) -> Self {
+        let vertex_source = fs::read_to_string("res/shaders/post_vertex.glsl").unwrap();
+
+        Self {
+            extract_program: post_program(
+                display,
+                &vertex_source,
+                Path::new("res/shaders/bloom_extract_fragment.glsl"),
+            ),
+            blur_program: post_program(
+                display,
+                &vertex_source,
+                Path::new("res/shaders/blur_fragment.glsl"),
+            ),
+            composite_program: post_program(
+                display,
+                &vertex_source,
+                Path::new("res/shaders/composite_fragment.glsl"),
+            ),
+            fxaa_program: post_program(
+                display,
+                &vertex_source,
+                Path::new("res/shaders/fxaa_fragment.glsl"),
+            ),
+            vertex_source,
+            pass_programs: HashMap::new(),
+            luts: HashMap::new(),
+            black: Texture2d::new(display, vec![vec![(0_u8, 0_u8, 0_u8, 255_u8)]]).unwrap(),
+            buffers: HashMap::new(),
+        }
+    }
+
+    // Creates buffers for every target size used this frame and drops the rest, and
+    // loads any shaders or LUTs the settings refer to
+    pub fn prepare(
+        &mut self,
+        display: &Display<WindowSurface>,
+        settings: &PostProcessResource,
+        anti_aliasing: &AntiAliasingResource,
+        sizes: &[(u32, u32)],
+    ) {
+        let samples = anti_aliasing.get_msaa_samples();
+
+        self.buffers
+            .retain(|size, buffers| sizes.contains(size) && buffers.samples == samples);
+        for size in sizes {
+            self.buffers
+                .entry(*size)
+                .or_insert_with(|| HdrBuffers::new(display, *size, samples));
+        }
+
+        for pass in &settings.passes {
+            if !self.pass_programs.contains_key(&pass.shader) {
+                let program = post_program(display, &self.vertex_source, &pass.shader);
+                self.pass_programs.insert(pass.shader.clone(), program);
+            }
+        }
+
+        if let Some(path) = &settings.color_grading_lut {
+            if !self.luts.contains_key(path) {
+                let image = image::open(path).unwrap().to_rgba8();
+                let dimensions = image.dimensions();
+                let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+
+                self.luts
+                    .insert(path.clone(), Texture2d::new(display, image).unwrap());
+            }
+        }
+    }
+
+    pub fn get_buffers(&self, size: (u32, u32)) -> &HdrBuffers {
+        &self.buffers[&size]
+    }
+
+    // Runs the post process chain on the HDR buffers of the given size and writes the
+    // tone mapped, sRGB encoded result to the target
+    pub fn apply<T: Surface>(
+        &self,
+        display: &Display<WindowSurface>,
+        size: (u32, u32),
+        settings: &PostProcessResource,
+        anti_aliasing: &AntiAliasingResource,
+        target: &mut T,
+    ) {
+        let buffers = &self.buffers[&size];
+        let (width, height) = size;
+
+        let mut current = &buffers.color;
+        let mut other = &buffers.swap;
+
+        for pass in &settings.passes {
+            let mut framebuffer = SimpleFrameBuffer::new(display, other).unwrap();
+
+            draw_fullscreen(
+                &mut framebuffer,
+                &self.pass_programs[&pass.shader],
+                &uniform! {
+                    color: sampled(current),
+                    resolution: [width as f32, height as f32],
+                    params: pass.params.to_array(),
+                },
+            );
+
+            std::mem::swap(&mut current, &mut other);
+        }
+
+        let bloom = settings.bloom.map(|bloom| {
+            let [bright, blurred] = &buffers.bloom;
+
+            draw_fullscreen(
+                &mut SimpleFrameBuffer::new(display, bright).unwrap(),
+                &self.extract_program,
+                &uniform! {
+                    scene: sampled(current),
+                    threshold: bloom.threshold,
+                },
+            );
+
+            for _ in 0..bloom.iterations {
+                draw_fullscreen(
+                    &mut SimpleFrameBuffer::new(display, blurred).unwrap(),
+                    &self.blur_program,
+                    &uniform! {
+                        image: sampled(bright),
+                        direction: [1.0_f32, 0.0],
+                    },
+                );
+                draw_fullscreen(
+                    &mut SimpleFrameBuffer::new(display, bright).unwrap(),
+                    &self.blur_program,
+                    &uniform! {
+                        image: sampled(blurred),
+                        direction: [0.0_f32, 1.0],
+                    },
+                );
+            }
+
+            (bright, bloom.intensity)
+        });
+
+        let lut = settings
+            .color_grading_lut
+            .as_ref()
+            .map(|path| &self.luts[path]);
+        let vignette = settings.vignette.unwrap_or_default();
+
+        let composite_uniforms = uniform! {
+            scene: sampled(current),
+            bloom: sampled(bloom.map_or(&self.black, |(texture, _)| texture)),
+            lut: sampled(lut.unwrap_or(&self.black)),
+            exposure: settings.exposure,
+            tone_mapping: match settings.tone_mapping {
+                ToneMapping::None => 0_i32,
+                ToneMapping::Reinhard => 1,
+                ToneMapping::Aces => 2,
+            },
+            bloom_intensity: bloom.map_or(0.0, |(_, intensity)| intensity),
+            lut_enabled: lut.is_some(),
+            lut_size: lut.map_or(1.0, |lut| lut.height() as f32),
+            vignette_intensity: settings.vignette.map_or(0.0, |_| vignette.intensity),
+            vignette_smoothness: vignette.smoothness,
+        };
+
+        // FXAA works on the final colors, so it goes after tone mapping
+        if anti_aliasing.fxaa {
+            draw_fullscreen(
+                &mut SimpleFrameBuffer::new(display, &buffers.ldr).unwrap(),
+                &self.composite_program,
+                &composite_uniforms,
+            );
+            draw_fullscreen(
+                target,
+                &self.fxaa_program,
+                &uniform! { image: sampled(&buffers.ldr) },
+            );
+        } else {
+            draw_fullscreen(target, &self.composite_program, &composite_uniforms);
+        }
+    }
+}
+
+fn hdr_texture(display: &Display<WindowSurface>, (width, height): (u32, u32)) -> Texture2d {
+    Texture2d::empty_with_format(
+        display,
+        UncompressedFloatFormat::F16F16F16F16,
+        MipmapsOption::NoMipmap,
+        width,
+        height,
+    )
+    .unwrap()
+}
+
+fn post_program(display: &Display<WindowSurface>, vertex_source: &str, fragment: &Path) -> Program {
+    Program::from_source(
+        display,
+        vertex_source,
+        fs::read_to_string(fragment).unwrap().as_str(),
+        None,
+    )
+    .unwrap()
+}
+
+fn sampled(texture: &Texture2d) -> Sampler<'_, Texture2d> {
+    texture
+        .sampled()
+        .magnify_filter(MagnifySamplerFilter::Linear)
+        .minify_filter(MinifySamplerFilter::Linear)
+        .wrap_function(SamplerWrapFunction::Clamp)
+}
+
+fn draw_fullscreen<T: Surface, U: Uniforms>(target: &mut T, program: &Program, uniforms: &U) {
+    target
+        .draw(
+            EmptyVertexAttributes { len: 3 },
+            NoIndices(PrimitiveType::TrianglesList),
+            program,
+            uniforms,
+            &Default::default(),
+        )
+        .unwrap();
+}
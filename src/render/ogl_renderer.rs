@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    cell::{Ref, RefCell},
     collections::HashMap,
     fs,
     num::NonZeroU32,
@@ -8,16 +9,41 @@ use std::{
 };
 
 use crate::{
-    components::{draw::DrawComponent, transform::TransformComponent},
-    resources::{camera::CameraResource, SunResource},
-    DrawData, DrawDescriptor, Mesh, Renderer,
+    components::{
+        animation_player::AnimationPlayerComponent,
+        camera::{CameraComponent, ClearSettings},
+        draw::DrawComponent,
+        morph_weights::MorphWeightsComponent,
+        transform::TransformComponent,
+    },
+    geometry::TriangleMesh,
+    resources::{
+        anti_aliasing::AntiAliasingResource, post_process::PostProcessResource, SunResource,
+    },
+    AlphaMode, AtlasId, ColorSpace, DrawData, DrawDescriptor, Mesh, MeshId, RenderTargetId,
+    Renderer, Skybox, Texture, TextureArrayId, TextureDescriptor, TextureSettings,
+};
+use brood::{query::filter, registry, result, system::System, Query, Views};
+use glam::{Mat4, Vec3A};
+
+use super::{
+    atlas::{TextureAtlas, ATLAS_PADDING},
+    environment::{constant_sh, CubeFaces},
+    mesh_data::{MeshData, VertexAttribute},
+    ogl_mesh::{OglMesh, VertexDefaults},
+    ogl_post_process::OglPostProcess,
+    ogl_texture::OglTexture,
+    shapes,
+    texture_data::TextureData,
 };
-use brood::{query::filter, registry, result, system::System, Views};
 use glium::{
-    glutin::surface::WindowSurface, implement_vertex, texture::RawImage2d, uniform, Display,
-    DrawParameters, Program, Surface, Texture2d,
+    framebuffer::SimpleFrameBuffer,
+    glutin::surface::WindowSurface,
+    texture::{CubeLayer, Cubemap, MipmapsOption, RawImage2d, UncompressedFloatFormat},
+    uniform,
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter, UniformValue, Uniforms},
+    BlitTarget, Display, DrawParameters, Program, Rect, Surface, Texture2d,
 };
-use glium::{index::IndexBufferAny, vertex::VertexBufferAny};
 use glutin::{
     config::ConfigTemplate,
     context::NotCurrentGlContext,
@@ -26,54 +52,114 @@ use glutin::{
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 use winit::window::Window;
 
-#[derive(Copy, Clone)]
-pub struct Vertex {
-    position: [f32; 3],
-    normal: [f32; 3],
-    tex_coords: [f32; 2],
-}
-implement_vertex!(Vertex, position, normal, tex_coords);
-
-impl Vertex {
-    pub fn new(
-        x: f32,
-        y: f32,
-        z: f32,
-        normal_x: f32,
-        normal_y: f32,
-        normal_z: f32,
-        u: f32,
-        v: f32,
-    ) -> Self {
-        Self {
-            position: [x, y, z],
-            normal: [normal_x, normal_y, normal_z],
-            tex_coords: [u, v],
-        }
-    }
-}
-
-struct OglMesh {
-    vertex_buffer: VertexBufferAny,
-    indices: Option<IndexBufferAny>,
+const DEFAULT_AMBIENT: f32 = 0.1;
+// Has to match the shader. Needs room for 2048 uniform components on top of the rest, which
+// desktop drivers have.
+pub const MAX_JOINTS: usize = 128;
+// Also has to match the shader, where the weights are packed four to a vec4
+pub const MAX_MORPH_TARGETS: usize = 32;
+
+struct OglTextureBinding {
+    texture: Rc<OglTexture>,
+    settings: TextureSettings,
+    // Offset and size of the atlas region the UVs are remapped into
+    uv_rect: [f32; 4],
+    layer: u32,
 }
 
 struct OglDrawData {
-    mesh: Rc<OglMesh>,
-    texture: Rc<Texture2d>,
+    // Shared with the renderer so custom meshes can be updated in place
+    mesh: Rc<RefCell<OglMesh>>,
+    texture: OglTextureBinding,
+    // Sampled with the same UVs as the texture, so it has to share its layout
+    normal_map: Option<OglTextureBinding>,
+    alpha_mode: AlphaMode,
 }
 
 impl DrawData for OglDrawData {
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+
+    fn get_shape(&self) -> Option<Ref<'_, TriangleMesh>> {
+        Some(Ref::map(self.mesh.borrow(), |mesh| &mesh.shape))
+    }
+}
+
+// Adds the joint matrices and morph target weights of a draw, which the uniform! macro
+// can't do since every array element is its own uniform
+struct AnimationUniforms<'a, U> {
+    uniforms: U,
+    joint_names: &'a [String],
+    joint_matrices: &'a [Mat4],
+    morph_names: &'a [String],
+    morph_weights: &'a [f32],
+}
+
+impl<U: Uniforms> Uniforms for AnimationUniforms<'_, U> {
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut output: F) {
+        self.uniforms.visit_values(&mut output);
+
+        for (name, matrix) in self.joint_names.iter().zip(self.joint_matrices) {
+            output(name, UniformValue::Mat4(matrix.to_cols_array_2d()));
+        }
+
+        for (name, weights) in self.morph_names.iter().zip(self.morph_weights.chunks(4)) {
+            let mut packed = [0.0; 4];
+            packed[..weights.len()].copy_from_slice(weights);
+            output(name, UniformValue::Vec4(packed));
+        }
+    }
+}
+
+// Everything about an entity a draw needs
+#[derive(Clone, Copy)]
+struct OglDrawable<'a> {
+    transform: &'a TransformComponent,
+    draw: &'a OglDrawData,
+    player: Option<&'a AnimationPlayerComponent>,
+    morph_weights: Option<&'a MorphWeightsComponent>,
+}
+
+struct OglRenderTarget {
+    color: Rc<OglTexture>,
+}
+
+struct OglCamera {
+    position: Vec3A,
+    view_projection: Mat4,
+    skybox_view_projection: Mat4,
+    viewport: Rect,
+    clear: ClearSettings,
+    target: Option<RenderTargetId>,
+    order: i32,
 }
 
 pub struct OglRenderer {
     display: Display<WindowSurface>,
     program: Program,
-    meshes: HashMap<Mesh, Weak<OglMesh>>,
-    textures: HashMap<Cow<'static, Path>, Weak<Texture2d>>,
+    // Meshes without these still draw with the defaults, but look wrong
+    required_attributes: Vec<VertexAttribute>,
+    skybox_program: Program,
+    skybox_mesh: OglMesh,
+    vertex_defaults: VertexDefaults,
+    skybox: Option<Cubemap>,
+    post_process: OglPostProcess,
+    ambient_sh: [[[f32; 3]; 3]; 3],
+    meshes: HashMap<Mesh, Weak<RefCell<OglMesh>>>,
+    custom_meshes: HashMap<MeshId, Rc<RefCell<OglMesh>>>,
+    next_mesh_id: u32,
+    textures: HashMap<(Cow<'static, Path>, ColorSpace, bool), Weak<OglTexture>>,
+    // Bound to whichever sampler a draw doesn't use
+    blank: OglTexture,
+    blank_array: OglTexture,
+    blank_float: Texture2d,
+    render_targets: HashMap<RenderTargetId, OglRenderTarget>,
+    atlases: HashMap<AtlasId, (Rc<OglTexture>, TextureAtlas)>,
+    texture_arrays: HashMap<TextureArrayId, Rc<OglTexture>>,
+    cameras: Vec<OglCamera>,
+    joint_uniform_names: Vec<String>,
+    morph_uniform_names: Vec<String>,
 }
 
 impl OglRenderer {
@@ -145,111 +231,162 @@ impl OglRenderer {
         )
         .unwrap();
 
+        // Only what the shader declares counts, so the list follows edits to it
+        let required_attributes = [VertexAttribute::Normal, VertexAttribute::TexCoords]
+            .into_iter()
+            .filter(|attribute| {
+                attribute
+                    .get_names()
+                    .iter()
+                    .all(|name| program.get_attribute(name).is_some())
+            })
+            .collect();
+
+        let skybox_program = Program::from_source(
+            &display,
+            fs::read_to_string("res/shaders/skybox_vertex.glsl")
+                .unwrap()
+                .as_str(),
+            fs::read_to_string("res/shaders/skybox_fragment.glsl")
+                .unwrap()
+                .as_str(),
+            None,
+        )
+        .unwrap();
+
         let mut meshes = HashMap::new();
-        meshes.insert(Mesh::Triangle, unsafe {
-            let rc = Rc::new(gen_triangle(&display));
-            let raw = Rc::into_raw(rc);
-            Rc::increment_strong_count(raw);
-            Weak::from_raw(raw)
-        });
-        meshes.insert(Mesh::Square, unsafe {
-            let rc = Rc::new(gen_square(&display));
-            let raw = Rc::into_raw(rc);
-            Rc::increment_strong_count(raw);
-            Weak::from_raw(raw)
-        });
-        meshes.insert(Mesh::Cube, unsafe {
-            let rc = Rc::new(gen_cube(&display));
-            let raw = Rc::into_raw(rc);
-            Rc::increment_strong_count(raw);
-            Weak::from_raw(raw)
-        });
+        for mesh in [Mesh::Triangle, Mesh::Square, Mesh::Cube] {
+            let data = shapes::generate(&mesh).unwrap();
+            meshes.insert(mesh, unsafe {
+                let rc = Rc::new(RefCell::new(OglMesh::new(&display, &data)));
+                let raw = Rc::into_raw(rc);
+                Rc::increment_strong_count(raw);
+                Weak::from_raw(raw)
+            });
+        }
 
+        let blank_settings = TextureSettings {
+            mipmap_filter: None,
+            ..Default::default()
+        };
         Self {
+            skybox_mesh: OglMesh::new(&display, &shapes::generate(&Mesh::Cube).unwrap()),
+            vertex_defaults: VertexDefaults::new(&display),
+            post_process: OglPostProcess::new(&display),
+            blank: OglTexture::from_data(&display, TextureData::blank(), &blank_settings),
+            blank_array: OglTexture::array(&display, vec![TextureData::blank()], &blank_settings),
+            blank_float: Texture2d::empty_with_format(
+                &display,
+                UncompressedFloatFormat::F32F32F32F32,
+                MipmapsOption::NoMipmap,
+                1,
+                1,
+            )
+            .unwrap(),
             display,
             program,
+            required_attributes,
+            skybox_program,
+            skybox: None,
+            ambient_sh: constant_sh(DEFAULT_AMBIENT),
             meshes,
+            custom_meshes: HashMap::new(),
+            next_mesh_id: 0,
             textures: HashMap::new(),
+            render_targets: HashMap::new(),
+            atlases: HashMap::new(),
+            texture_arrays: HashMap::new(),
+            cameras: Vec::new(),
+            joint_uniform_names: (0..MAX_JOINTS)
+                .map(|i| format!("joint_matrices[{i}]"))
+                .collect(),
+            morph_uniform_names: (0..MAX_MORPH_TARGETS / 4)
+                .map(|i| format!("morph_weights[{i}]"))
+                .collect(),
         }
     }
 
-    fn load_mesh(&mut self, mesh_name: &Mesh) -> Rc<OglMesh> {
+    fn load_mesh(&mut self, mesh_name: &Mesh) -> Rc<RefCell<OglMesh>> {
+        if let Mesh::Custom(id) = mesh_name {
+            if let Some(mesh) = self.custom_meshes.get(id) {
+                return mesh.clone();
+            }
+
+            eprintln!("Warning: mesh {id:?} doesn't exist, drawing a cube instead");
+            return self.load_mesh(&Mesh::Cube);
+        }
+
         if let Some(i) = self.meshes.get(mesh_name) {
             if let Some(strong) = i.upgrade() {
                 return strong;
             }
         }
 
-        let mesh = match mesh_name {
-            Mesh::Gltf(path) => {
-                let gltf = easy_gltf::load(path).unwrap();
-                let scene = &gltf[0];
-                let model = &scene.models[0];
-
-                let mut vertices = Vec::with_capacity(model.vertices().len());
-
-                for i in model.vertices() {
-                    vertices.push(Vertex::new(
-                        i.position.x,
-                        i.position.y,
-                        i.position.z,
-                        i.normal.x,
-                        i.normal.y,
-                        i.normal.z,
-                        i.tex_coords.x,
-                        i.tex_coords.y,
-                    ))
-                }
+        let mesh = Rc::new(RefCell::new(OglMesh::new(
+            &self.display,
+            &mesh_name.load_data(),
+        )));
+        self.meshes.insert(mesh_name.clone(), Rc::downgrade(&mesh));
 
-                let mut indices = None;
-
-                if let Some(i) = model.indices() {
-                    indices = Some(
-                        glium::IndexBuffer::new(
-                            &self.display,
-                            glium::index::PrimitiveType::TrianglesList,
-                            &i,
-                        )
-                        .unwrap()
-                        .into(),
-                    );
-                }
+        mesh
+    }
 
-                Rc::new(OglMesh {
-                    vertex_buffer: glium::VertexBuffer::new(&self.display, &vertices)
-                        .unwrap()
-                        .into(),
-                    indices,
-                })
-            }
-            _ => panic!("Internal mesh not in hashmap?"),
+    fn load_texture(&mut self, descriptor: &TextureDescriptor) -> OglTextureBinding {
+        let settings = descriptor.settings;
+        let binding = |texture, uv_rect, layer| OglTextureBinding {
+            texture,
+            settings,
+            uv_rect,
+            layer,
         };
+        let full_rect = [0.0, 0.0, 1.0, 1.0];
 
-        self.meshes.insert(mesh_name.clone(), Rc::downgrade(&mesh));
+        let texture_name = match &descriptor.texture {
+            Texture::File(path) => path,
+            Texture::RenderTarget(id) => {
+                return binding(self.render_targets[id].color.clone(), full_rect, 0)
+            }
+            Texture::Atlas { atlas, region } => {
+                let (texture, atlas) = &self.atlases[atlas];
+                let uv_rect = match region.map(|region| (region, atlas.regions.get(region))) {
+                    Some((_, Some(region))) => [
+                        region.offset.x,
+                        region.offset.y,
+                        region.size.x,
+                        region.size.y,
+                    ],
+                    Some((region, None)) => {
+                        eprintln!("Warning: atlas has no region {region}, using all of it");
+                        full_rect
+                    }
+                    None => full_rect,
+                };
+
+                return binding(texture.clone(), uv_rect, 0);
+            }
+            Texture::ArrayLayer { array, layer } => {
+                return binding(self.texture_arrays[array].clone(), full_rect, *layer)
+            }
+        };
 
-        mesh
-    }
+        // Sampling is set per draw, only what changes the uploaded texture is part of the key
+        let key = (
+            texture_name.clone(),
+            settings.color_space,
+            settings.mipmap_filter.is_some(),
+        );
 
-    fn load_texture(&mut self, texture_name: &Cow<'static, Path>) -> Rc<Texture2d> {
-        if let Some(i) = self.textures.get(texture_name) {
+        if let Some(i) = self.textures.get(&key) {
             if let Some(strong) = i.upgrade() {
-                return strong;
+                return binding(strong, full_rect, 0);
             }
         }
 
-        let image = image::io::Reader::open(texture_name)
-            .unwrap()
-            .decode()
-            .unwrap()
-            .to_rgba8();
-        let image_dimensions = image.dimensions();
-        let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_dimensions);
-        let texture = Rc::new(Texture2d::new(&self.display, image).unwrap());
+        let texture = Rc::new(OglTexture::load(&self.display, texture_name, &settings));
 
-        self.textures
-            .insert(texture_name.clone(), Rc::downgrade(&texture));
+        self.textures.insert(key, Rc::downgrade(&texture));
 
-        texture
+        binding(texture, full_rect, 0)
     }
 }
 
@@ -258,23 +395,251 @@ impl Renderer for OglRenderer {
         &mut self,
         world: &mut brood::World<crate::components::Registry, crate::resources::Resources>,
     ) {
+        self.cameras.clear();
+
+        for result!(transform, camera) in world
+            .query(Query::<Views!(&TransformComponent, &CameraComponent), filter::None>::new())
+            .iter
+        {
+            if !camera.active {
+                continue;
+            }
+
+            let (width, height) = self.get_target_size(camera.target);
+
+            let viewport = Rect {
+                left: (camera.viewport.x * width as f32) as u32,
+                bottom: ((1.0 - camera.viewport.y - camera.viewport.height) * height as f32)
+                    as u32,
+                width: (camera.viewport.width * width as f32) as u32,
+                height: (camera.viewport.height * height as f32) as u32,
+            };
+
+            if viewport.width == 0 || viewport.height == 0 {
+                continue;
+            }
+
+            let projection =
+                camera.get_projection(viewport.width as f32, viewport.height as f32);
+
+            self.cameras.push(OglCamera {
+                position: transform.translation,
+                view_projection: projection * transform.get_mat().inverse(),
+                skybox_view_projection: projection * Mat4::from_quat(transform.rotation.inverse()),
+                viewport,
+                clear: camera.clear,
+                target: camera.target,
+                order: camera.order,
+            });
+        }
+
+        self.cameras.sort_by_key(|camera| camera.order);
+
         world.run_system(self);
     }
 
     fn load(&mut self, descriptor: &DrawDescriptor) -> DrawComponent {
+        let mesh = self.load_mesh(&descriptor.mesh);
+
+        let missing: Vec<_> = self
+            .required_attributes
+            .iter()
+            .filter(|attribute| !mesh.borrow().has_attribute(**attribute))
+            .collect();
+        if !missing.is_empty() {
+            eprintln!(
+                "Warning: {:?} has no {missing:?}, drawing it with the defaults",
+                descriptor.mesh
+            );
+        }
+
+        // Normal maps need tangents, and are left out without them
+        let mut normal_map = descriptor.normal_map.as_ref();
+        if normal_map.is_some() && !mesh.borrow().has_attribute(VertexAttribute::Tangent) {
+            eprintln!(
+                "Warning: {:?} has no tangents, drawing it without its normal map",
+                descriptor.mesh
+            );
+            normal_map = None;
+        }
+
         DrawComponent {
             inner: Box::new(OglDrawData {
-                mesh: self.load_mesh(&descriptor.mesh),
+                mesh,
                 texture: self.load_texture(&descriptor.texture),
+                normal_map: normal_map.map(|normal_map| self.load_texture(normal_map)),
+                alpha_mode: descriptor.alpha_mode,
             }),
         }
     }
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> RenderTargetId {
+        let id = RenderTargetId(self.render_targets.len() as u32);
+
+        self.render_targets.insert(
+            id,
+            OglRenderTarget {
+                color: Rc::new(OglTexture::render_target(&self.display, width, height)),
+            },
+        );
+
+        id
+    }
+
+    fn set_skybox(&mut self, skybox: Option<&Skybox>) {
+        let Some(skybox) = skybox else {
+            self.skybox = None;
+            self.ambient_sh = constant_sh(DEFAULT_AMBIENT);
+            return;
+        };
+
+        let faces = CubeFaces::load(skybox);
+        let cubemap = Cubemap::empty_with_format(
+            &self.display,
+            UncompressedFloatFormat::F16F16F16,
+            MipmapsOption::NoMipmap,
+            faces.size,
+        )
+        .unwrap();
+
+        let layers = [
+            CubeLayer::PositiveX,
+            CubeLayer::NegativeX,
+            CubeLayer::PositiveY,
+            CubeLayer::NegativeY,
+            CubeLayer::PositiveZ,
+            CubeLayer::NegativeZ,
+        ];
+
+        // Cubemap faces can't be written to directly, so each is uploaded and blitted over
+        for (layer, data) in layers.into_iter().zip(&faces.faces) {
+            let face = Texture2d::new(
+                &self.display,
+                RawImage2d::from_raw_rgb(data.clone(), (faces.size, faces.size)),
+            )
+            .unwrap();
+            let target =
+                SimpleFrameBuffer::new(&self.display, cubemap.main_level().image(layer)).unwrap();
+
+            face.as_surface().blit_whole_color_to(
+                &target,
+                &BlitTarget {
+                    left: 0,
+                    bottom: 0,
+                    width: faces.size as i32,
+                    height: faces.size as i32,
+                },
+                MagnifySamplerFilter::Nearest,
+            );
+        }
+
+        self.ambient_sh = faces.irradiance_sh();
+        self.skybox = Some(cubemap);
+    }
+
+    fn create_atlas(
+        &mut self,
+        textures: &[Cow<'static, Path>],
+        settings: &TextureSettings,
+    ) -> (AtlasId, TextureAtlas) {
+        let images: Vec<_> = textures
+            .iter()
+            .map(|path| match TextureData::load(path) {
+                TextureData::Rgba8 {
+                    width,
+                    height,
+                    data,
+                } => {
+                    // Still packed, as an empty region, so the indices of the others hold
+                    if width == 0 || height == 0 {
+                        eprintln!("Warning: {path:?} is empty, its atlas region will be too");
+                    }
+                    (width, height, data)
+                }
+                _ => {
+                    eprintln!(
+                        "Warning: {path:?} is not an 8 bit uncompressed image, which atlases \
+                         need, so its region is blank"
+                    );
+                    (1, 1, vec![255; 4])
+                }
+            })
+            .collect();
+        let images: Vec<_> = images
+            .iter()
+            .map(|(width, height, data)| (*width, *height, data.as_slice()))
+            .collect();
+
+        let (atlas, data) = TextureAtlas::build(&images, ATLAS_PADDING);
+        let texture = OglTexture::from_data(
+            &self.display,
+            TextureData::Rgba8 {
+                width: atlas.width,
+                height: atlas.height,
+                data,
+            },
+            settings,
+        );
+
+        let id = AtlasId(self.atlases.len() as u32);
+        self.atlases.insert(id, (Rc::new(texture), atlas.clone()));
+
+        (id, atlas)
+    }
+
+    fn create_texture_array(
+        &mut self,
+        layers: &[Cow<'static, Path>],
+        settings: &TextureSettings,
+    ) -> TextureArrayId {
+        let layers = layers.iter().map(|path| TextureData::load(path)).collect();
+        let texture = OglTexture::array(&self.display, layers, settings);
+
+        let id = TextureArrayId(self.texture_arrays.len() as u32);
+        self.texture_arrays.insert(id, Rc::new(texture));
+
+        id
+    }
+
+    fn create_mesh(&mut self, data: &MeshData) -> MeshId {
+        let id = MeshId(self.next_mesh_id);
+        self.next_mesh_id += 1;
+
+        self.custom_meshes
+            .insert(id, Rc::new(RefCell::new(OglMesh::new(&self.display, data))));
+
+        id
+    }
+
+    fn update_mesh(&mut self, id: MeshId, data: &MeshData) {
+        match self.custom_meshes.get(&id) {
+            Some(mesh) => mesh.borrow_mut().update(&self.display, data),
+            None => eprintln!("Warning: mesh {id:?} doesn't exist and can't be updated"),
+        }
+    }
+
+    fn update_mesh_vertices(&mut self, id: MeshId, data: &MeshData) {
+        match self.custom_meshes.get(&id) {
+            Some(mesh) => mesh.borrow_mut().update_vertices(&self.display, data),
+            None => eprintln!("Warning: mesh {id:?} doesn't exist and can't be updated"),
+        }
+    }
+
+    fn remove_mesh(&mut self, id: MeshId) {
+        self.custom_meshes.remove(&id);
+    }
 }
 
 impl System for OglRenderer {
     type Filter = filter::None;
-    type Views<'a> = Views!(&'a TransformComponent, &'a DrawComponent);
-    type ResourceViews<'a> = Views!(&'a CameraResource, &'a SunResource);
+    type Views<'a> = Views!(
+        &'a TransformComponent,
+        &'a DrawComponent,
+        Option<&'a AnimationPlayerComponent>,
+        Option<&'a MorphWeightsComponent>
+    );
+    type ResourceViews<'a> =
+        Views!(&'a SunResource, &'a PostProcessResource, &'a AntiAliasingResource);
     type EntryViews<'a> = Views!();
 
     fn run<'a, R, S, I, E>(
@@ -292,147 +657,279 @@ impl System for OglRenderer {
         R: registry::Registry,
         I: Iterator<Item = Self::Views<'a>>,
     {
-        let mut target = self.display.draw();
-        target.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+        let result!(sun, post_process, anti_aliasing) = query_result.resources;
+
+        let drawables: Vec<_> = query_result
+            .iter
+            .map(
+                |result!(transform, draw, player, morph_weights)| OglDrawable {
+                    transform,
+                    draw: draw.inner.as_any().downcast_ref::<OglDrawData>().unwrap(),
+                    player,
+                    morph_weights,
+                },
+            )
+            .collect();
+
+        let mut targets = Vec::new();
+        for camera in &self.cameras {
+            if !targets.contains(&camera.target) {
+                targets.push(camera.target);
+            }
+        }
+
+        // Render targets go first so window cameras can sample what they hold this frame
+        targets.sort_by_key(|target| target.is_none());
+
+        let sizes: Vec<_> = targets
+            .iter()
+            .map(|target| self.get_target_size(*target))
+            .collect();
+        self.post_process
+            .prepare(&self.display, post_process, anti_aliasing, &sizes);
+
+        let mut frame = self.display.draw();
+
+        for (target, size) in targets.into_iter().zip(sizes) {
+            let buffers = self.post_process.get_buffers(size);
+            let mut hdr = buffers.get_framebuffer(&self.display);
+            hdr.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+
+            for camera in self.cameras.iter().filter(|camera| camera.target == target) {
+                self.draw_camera(&mut hdr, camera, &drawables, sun);
+            }
+
+            buffers.resolve(&self.display, &hdr);
+
+            match target {
+                Some(id) => {
+                    let mut framebuffer =
+                        self.render_targets[&id].color.get_framebuffer(&self.display);
+
+                    self.post_process.apply(
+                        &self.display,
+                        size,
+                        post_process,
+                        anti_aliasing,
+                        &mut framebuffer,
+                    );
+                }
+                None => self.post_process.apply(
+                    &self.display,
+                    size,
+                    post_process,
+                    anti_aliasing,
+                    &mut frame,
+                ),
+            }
+        }
+
+        frame.finish().unwrap();
+    }
+}
+
+impl OglRenderer {
+    fn get_target_size(&self, target: Option<RenderTargetId>) -> (u32, u32) {
+        match target {
+            Some(id) => self.render_targets[&id].color.dimensions(),
+            None => self.display.get_framebuffer_dimensions(),
+        }
+    }
+
+    fn draw_camera<T: Surface>(
+        &self,
+        target: &mut T,
+        camera: &OglCamera,
+        drawables: &[OglDrawable],
+        sun: &SunResource,
+    ) {
+        target.clear(
+            Some(&camera.viewport),
+            camera.clear.color.map(|c| (c[0], c[1], c[2], c[3])),
+            false,
+            camera.clear.depth.then_some(1.0),
+            None,
+        );
+
+        let mut opaque = Vec::new();
+        let mut blended = Vec::new();
+
+        for drawable in drawables {
+            match drawable.draw.alpha_mode {
+                AlphaMode::Opaque | AlphaMode::Mask(_) => opaque.push(drawable),
+                AlphaMode::Blend => {
+                    let model_mat = drawable.transform.get_mat();
+                    let center = model_mat
+                        .transform_point3a(drawable.draw.mesh.borrow().shape.bounds.center());
+
+                    blended.push((camera.position.distance_squared(center), drawable));
+                }
+            }
+        }
+
+        // Back to front, so everything behind a blended surface is already there
+        blended.sort_by(|a, b| b.0.total_cmp(&a.0));
 
-        let draw_parameters = DrawParameters {
+        let opaque_parameters = DrawParameters {
             depth: glium::Depth {
                 test: glium::draw_parameters::DepthTest::IfLess,
                 write: true,
                 ..Default::default()
             },
+            viewport: Some(camera.viewport),
             ..Default::default()
         };
 
-        let result!(camera, sun) = query_result.resources;
-
-        for result!(transform, draw) in query_result.iter {
-            let ogl_draw = draw.inner.as_any().downcast_ref::<OglDrawData>().unwrap();
-            match &ogl_draw.mesh.indices {
-                Some(i) => {
-                    target
-                        .draw(
-                            &ogl_draw.mesh.vertex_buffer,
-                            i,
-                            &self.program,
-                            &uniform! {
-                                camera_mat: camera.get_mat_array(),
-                                model_mat: transform.get_mat_array(),
-                                tex: ogl_draw.texture.as_ref(),
-                                light_pos: sun.0.to_array(),
-                            },
-                            &draw_parameters,
-                        )
-                        .unwrap();
-                }
-                None => {
-                    target
-                        .draw(
-                            &ogl_draw.mesh.vertex_buffer,
-                            glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
-                            &self.program,
-                            &uniform! {
-                                camera_mat: camera.get_mat_array(),
-                                model_mat: transform.get_mat_array(),
-                                tex: ogl_draw.texture.as_ref(),
-                                light_pos: sun.0.to_array(),
-                            },
-                            &draw_parameters,
-                        )
-                        .unwrap();
-                }
-            }
+        for drawable in opaque {
+            self.draw_mesh(target, camera, drawable, sun, &opaque_parameters);
         }
 
-        target.finish().unwrap();
-    }
-}
+        // Drawn after opaque geometry so it's only shaded where no geometry was, and before
+        // blended geometry since that doesn't write depth
+        if let Some(skybox) = self.skybox.as_ref().filter(|_| camera.clear.skybox) {
+            target
+                .draw(
+                    &self.skybox_mesh.positions,
+                    glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                    &self.skybox_program,
+                    &uniform! {
+                        view_rotation_mat: camera.skybox_view_projection.to_cols_array_2d(),
+                        skybox: skybox,
+                    },
+                    &DrawParameters {
+                        depth: glium::Depth {
+                            test: glium::draw_parameters::DepthTest::IfLessOrEqual,
+                            write: false,
+                            ..Default::default()
+                        },
+                        viewport: Some(camera.viewport),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
 
-fn gen_triangle(display: &Display<WindowSurface>) -> OglMesh {
-    let triangle_verts = vec![
-        Vertex::new(-0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0),
-        Vertex::new(0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0),
-        Vertex::new(0.0, 0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0),
-    ];
+        let blend_parameters = DrawParameters {
+            depth: glium::Depth {
+                test: glium::draw_parameters::DepthTest::IfLess,
+                write: false,
+                ..Default::default()
+            },
+            blend: glium::Blend::alpha_blending(),
+            viewport: Some(camera.viewport),
+            ..Default::default()
+        };
 
-    OglMesh {
-        vertex_buffer: glium::VertexBuffer::new(display, &triangle_verts)
-            .unwrap()
-            .into(),
-        indices: None,
+        for (_, drawable) in blended {
+            self.draw_mesh(target, camera, drawable, sun, &blend_parameters);
+        }
     }
-}
 
-fn gen_square(display: &Display<WindowSurface>) -> OglMesh {
-    let square_verts = vec![
-        Vertex::new(0.5, 0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0),
-        Vertex::new(0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0),
-        Vertex::new(-0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0),
-        Vertex::new(-0.5, 0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0),
-    ];
+    fn draw_mesh<T: Surface>(
+        &self,
+        target: &mut T,
+        camera: &OglCamera,
+        drawable: &OglDrawable,
+        sun: &SunResource,
+        draw_parameters: &DrawParameters,
+    ) {
+        let (transform, ogl_draw) = (drawable.transform, drawable.draw);
+        let mesh = ogl_draw.mesh.borrow();
+        // Only meshes with joints and weights are skinned, the default attribute would put
+        // everything on the first joint
+        let joint_matrices = match drawable.player {
+            Some(player) if mesh.has_attribute(VertexAttribute::Skin) => {
+                &player.joint_matrices[..player.joint_matrices.len().min(MAX_JOINTS)]
+            }
+            _ => &[],
+        };
+        let morph_weights = match drawable.morph_weights {
+            Some(morph_weights) if mesh.morph_target_count > 0 => {
+                let count = mesh.morph_target_count.min(MAX_MORPH_TARGETS);
+                &morph_weights.weights[..morph_weights.weights.len().min(count)]
+            }
+            _ => &[],
+        };
+        // Unused samplers still need something bound
+        let morph_targets = mesh.morph_targets.as_ref().unwrap_or(&self.blank_float);
 
-    let square_indices: Vec<u32> = vec![0, 1, 3, 1, 2, 3];
+        let texture = &ogl_draw.texture;
+        let tex_is_array = texture.texture.is_array();
 
-    OglMesh {
-        vertex_buffer: glium::VertexBuffer::new(display, &square_verts)
-            .unwrap()
-            .into(),
-        indices: Some(
-            glium::IndexBuffer::new(
-                display,
-                glium::index::PrimitiveType::TrianglesList,
-                &square_indices,
-            )
-            .unwrap()
-            .into(),
-        ),
-    }
-}
+        // Something has to be bound to every sampler even when unused
+        let (tex, tex_array) = if tex_is_array {
+            (&self.blank, &*texture.texture)
+        } else {
+            (&*texture.texture, &self.blank_array)
+        };
+        let normal_map = ogl_draw.normal_map.as_ref().map_or_else(
+            || self.blank.sampled(&texture.settings),
+            |normal_map| normal_map.texture.sampled(&normal_map.settings),
+        );
+
+        let uniforms = uniform! {
+            camera_mat: camera.view_projection.to_cols_array_2d(),
+            model_mat: transform.get_mat_array(),
+            uv_rect: texture.uv_rect,
+            tex: tex.sampled(&texture.settings),
+            tex_array: tex_array.sampled(&texture.settings),
+            tex_is_array: tex_is_array,
+            tex_layer: texture.layer as f32,
+            tex_flip_v: texture.texture.is_flipped(),
+            normal_map: normal_map,
+            normal_map_flip_v: ogl_draw
+                .normal_map
+                .as_ref()
+                .is_some_and(|normal_map| normal_map.texture.is_flipped()),
+            normal_mapping: ogl_draw.normal_map.is_some(),
+            light_pos: sun.0.to_array(),
+            ambient_sh_r: self.ambient_sh[0],
+            ambient_sh_g: self.ambient_sh[1],
+            ambient_sh_b: self.ambient_sh[2],
+            alpha_cutoff: match ogl_draw.alpha_mode {
+                AlphaMode::Mask(cutoff) => cutoff,
+                _ => 0.0,
+            },
+            alpha_blend: ogl_draw.alpha_mode == AlphaMode::Blend,
+            skinning: !joint_matrices.is_empty(),
+            morph_targets: morph_targets
+                .sampled()
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Nearest),
+            morph_target_count: morph_weights.len() as i32,
+            morph_vertex_count: mesh.positions.len() as i32,
+        };
+        let uniforms = AnimationUniforms {
+            uniforms,
+            joint_names: &self.joint_uniform_names,
+            joint_matrices,
+            morph_names: &self.morph_uniform_names,
+            morph_weights,
+        };
 
-fn gen_cube(display: &Display<WindowSurface>) -> OglMesh {
-    let cube_verts = vec![
-        Vertex::new(-0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 0.0),
-        Vertex::new(0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 0.0),
-        Vertex::new(0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 1.0),
-        Vertex::new(0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 1.0),
-        Vertex::new(-0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 1.0),
-        Vertex::new(-0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 0.0),
-        Vertex::new(-0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 0.0),
-        Vertex::new(0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 0.0),
-        Vertex::new(0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 1.0),
-        Vertex::new(0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 1.0),
-        Vertex::new(-0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 1.0),
-        Vertex::new(-0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 0.0),
-        Vertex::new(-0.5, 0.5, 0.5, -1.0, 0.0, 0.0, 1.0, 0.0),
-        Vertex::new(-0.5, 0.5, -0.5, -1.0, 0.0, 0.0, 1.0, 1.0),
-        Vertex::new(-0.5, -0.5, -0.5, -1.0, 0.0, 0.0, 0.0, 1.0),
-        Vertex::new(-0.5, -0.5, -0.5, -1.0, 0.0, 0.0, 0.0, 1.0),
-        Vertex::new(-0.5, -0.5, 0.5, -1.0, 0.0, 0.0, 0.0, 0.0),
-        Vertex::new(-0.5, 0.5, 0.5, -1.0, 0.0, 0.0, 1.0, 0.0),
-        Vertex::new(0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 1.0, 0.0),
-        Vertex::new(0.5, 0.5, -0.5, 1.0, 0.0, 0.0, 1.0, 1.0),
-        Vertex::new(0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.0, 1.0),
-        Vertex::new(0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.0, 1.0),
-        Vertex::new(0.5, -0.5, 0.5, 1.0, 0.0, 0.0, 0.0, 0.0),
-        Vertex::new(0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 1.0, 0.0),
-        Vertex::new(-0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.0, 1.0),
-        Vertex::new(0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 1.0, 1.0),
-        Vertex::new(0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 1.0, 0.0),
-        Vertex::new(0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 1.0, 0.0),
-        Vertex::new(-0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 0.0, 0.0),
-        Vertex::new(-0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.0, 1.0),
-        Vertex::new(-0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.0, 1.0),
-        Vertex::new(0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 1.0, 1.0),
-        Vertex::new(0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 1.0, 0.0),
-        Vertex::new(0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 1.0, 0.0),
-        Vertex::new(-0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 0.0, 0.0),
-        Vertex::new(-0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.0, 1.0),
-    ];
-
-    OglMesh {
-        vertex_buffer: glium::VertexBuffer::new(display, &cube_verts)
-            .unwrap()
-            .into(),
-        indices: None,
+        match &mesh.indices {
+            Some(i) => {
+                target
+                    .draw(
+                        mesh.get_vertices(&self.vertex_defaults),
+                        i,
+                        &self.program,
+                        &uniforms,
+                        draw_parameters,
+                    )
+                    .unwrap();
+            }
+            None => {
+                target
+                    .draw(
+                        mesh.get_vertices(&self.vertex_defaults),
+                        glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                        &self.program,
+                        &uniforms,
+                        draw_parameters,
+                    )
+                    .unwrap();
+            }
+        }
     }
 }
@@ -0,0 +1,346 @@
+use std::path::Path;
+
+use glium::{
+    framebuffer::SimpleFrameBuffer,
+    glutin::surface::WindowSurface,
+    texture::{
+        CompressedFormat, CompressedMipmapsOption, CompressedSrgbFormat, CompressedSrgbTexture2d,
+        CompressedTexture2d, MipmapsOption, RawImage2d, SrgbFormat, SrgbTexture2d,
+        SrgbTexture2dArray, Texture2dArray, UncompressedFloatFormat,
+    },
+    uniforms::{
+        AsUniformValue, MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior,
+        SamplerWrapFunction, UniformValue,
+    },
+    Display, Rect, Texture2d,
+};
+
+use super::{
+    texture_data::{BlockFormat, TextureData},
+    ColorSpace, FilterMode, TextureSettings, WrapMode,
+};
+
+// sRGB textures are decoded to linear by the GPU when sampled, before filtering. Compressed
+// textures carry whether they have to be sampled with V flipped.
+pub enum OglTexture {
+    Linear(Texture2d),
+    Srgb(SrgbTexture2d),
+    Compressed(CompressedTexture2d, bool),
+    CompressedSrgb(CompressedSrgbTexture2d, bool),
+    Array(Texture2dArray),
+    SrgbArray(SrgbTexture2dArray),
+}
+
+impl OglTexture {
+    pub fn load(display: &Display<WindowSurface>, path: &Path, settings: &TextureSettings) -> Self {
+        Self::from_data(display, TextureData::load(path), settings)
+    }
+
+    pub fn from_data(
+        display: &Display<WindowSurface>,
+        data: TextureData,
+        settings: &TextureSettings,
+    ) -> Self {
+        let mipmaps = match settings.mipmap_filter {
+            Some(_) => MipmapsOption::AutoGeneratedMipmaps,
+            None => MipmapsOption::NoMipmap,
+        };
+
+        match data {
+            TextureData::Rgba8 {
+                width,
+                height,
+                data,
+            } => {
+                let image = RawImage2d::from_raw_rgba(data, (width, height));
+
+                match settings.color_space {
+                    ColorSpace::Linear => {
+                        Self::Linear(Texture2d::with_mipmaps(display, image, mipmaps).unwrap())
+                    }
+                    ColorSpace::Srgb => {
+                        Self::Srgb(SrgbTexture2d::with_mipmaps(display, image, mipmaps).unwrap())
+                    }
+                }
+            }
+            // Float images are always linear
+            TextureData::RgbaF32 {
+                width,
+                height,
+                data,
+            } => Self::Linear(
+                Texture2d::with_format(
+                    display,
+                    RawImage2d::from_raw_rgba(data, (width, height)),
+                    UncompressedFloatFormat::F16F16F16F16,
+                    mipmaps,
+                )
+                .unwrap(),
+            ),
+            TextureData::Compressed {
+                format,
+                srgb,
+                flip_v,
+                width,
+                height,
+                levels,
+            } => {
+                if levels.is_empty() {
+                    eprintln!("Warning: compressed texture without levels, using a blank one");
+                    return Self::from_data(display, TextureData::blank(), settings);
+                }
+
+                // Compressed textures can't have mipmaps generated, only the stored ones are used
+                let mipmaps = CompressedMipmapsOption::EmptyMipmapsMax(levels.len() as u32 - 1);
+                let srgb_format = match format {
+                    BlockFormat::Bc1 => Some(CompressedSrgbFormat::S3tcDxt1Alpha),
+                    BlockFormat::Bc2 => Some(CompressedSrgbFormat::S3tcDxt3Alpha),
+                    BlockFormat::Bc3 => Some(CompressedSrgbFormat::S3tcDxt5Alpha),
+                    BlockFormat::Bc7 => Some(CompressedSrgbFormat::Bptc),
+                    _ => None,
+                }
+                .filter(|_| srgb || settings.color_space == ColorSpace::Srgb);
+
+                match srgb_format {
+                    Some(format) => {
+                        let texture = CompressedSrgbTexture2d::with_compressed_data(
+                            display, &levels[0], width, height, format, mipmaps,
+                        )
+                        .unwrap();
+
+                        for (i, level) in levels.iter().enumerate().skip(1) {
+                            let (rect, width, height) = level_rect(width, height, i);
+                            texture
+                                .mipmap(i as u32)
+                                .unwrap()
+                                .write_compressed_data(rect, level, width, height, format)
+                                .unwrap();
+                        }
+
+                        Self::CompressedSrgb(texture, flip_v)
+                    }
+                    None => {
+                        let format = match format {
+                            BlockFormat::Bc1 => CompressedFormat::S3tcDxt1Alpha,
+                            BlockFormat::Bc2 => CompressedFormat::S3tcDxt3Alpha,
+                            BlockFormat::Bc3 => CompressedFormat::S3tcDxt5Alpha,
+                            BlockFormat::Bc4 => CompressedFormat::RgtcFormatU,
+                            BlockFormat::Bc5 => CompressedFormat::RgtcFormatUU,
+                            BlockFormat::Bc6hUnsigned => CompressedFormat::BptcUnsignedFloat3,
+                            BlockFormat::Bc6hSigned => CompressedFormat::BptcSignedFloat3,
+                            BlockFormat::Bc7 => CompressedFormat::BptcUnorm4,
+                        };
+
+                        let texture = CompressedTexture2d::with_compressed_data(
+                            display, &levels[0], width, height, format, mipmaps,
+                        )
+                        .unwrap();
+
+                        for (i, level) in levels.iter().enumerate().skip(1) {
+                            let (rect, width, height) = level_rect(width, height, i);
+                            texture
+                                .mipmap(i as u32)
+                                .unwrap()
+                                .write_compressed_data(rect, level, width, height, format)
+                                .unwrap();
+                        }
+
+                        Self::Compressed(texture, flip_v)
+                    }
+                }
+            }
+        }
+    }
+
+    // Every layer is resized to the size of the first one
+    pub fn array(
+        display: &Display<WindowSurface>,
+        layers: Vec<TextureData>,
+        settings: &TextureSettings,
+    ) -> Self {
+        let mipmaps = match settings.mipmap_filter {
+            Some(_) => MipmapsOption::AutoGeneratedMipmaps,
+            None => MipmapsOption::NoMipmap,
+        };
+
+        let mut size = None;
+        let images: Vec<_> = layers
+            .into_iter()
+            .map(|layer| {
+                let (width, height, data) = match layer {
+                    TextureData::Rgba8 {
+                        width,
+                        height,
+                        data,
+                    } => (width, height, data),
+                    _ => {
+                        eprintln!(
+                            "Warning: only 8 bit uncompressed images can be used in texture \
+                             arrays, using a blank layer"
+                        );
+                        (1, 1, vec![255; 4])
+                    }
+                };
+
+                let (target_width, target_height) = *size.get_or_insert((width, height));
+                let data = if (width, height) == (target_width, target_height) {
+                    data
+                } else {
+                    image::imageops::resize(
+                        &image::RgbaImage::from_raw(width, height, data).unwrap(),
+                        target_width,
+                        target_height,
+                        image::imageops::FilterType::Triangle,
+                    )
+                    .into_raw()
+                };
+
+                RawImage2d::from_raw_rgba(data, (target_width, target_height))
+            })
+            .collect();
+
+        match settings.color_space {
+            ColorSpace::Linear => {
+                Self::Array(Texture2dArray::with_mipmaps(display, images, mipmaps).unwrap())
+            }
+            ColorSpace::Srgb => {
+                Self::SrgbArray(SrgbTexture2dArray::with_mipmaps(display, images, mipmaps).unwrap())
+            }
+        }
+    }
+
+    // An 8 bit sRGB texture that can be rendered into
+    pub fn render_target(display: &Display<WindowSurface>, width: u32, height: u32) -> Self {
+        Self::Srgb(
+            SrgbTexture2d::empty_with_format(
+                display,
+                SrgbFormat::U8U8U8U8,
+                MipmapsOption::NoMipmap,
+                width,
+                height,
+            )
+            .unwrap(),
+        )
+    }
+
+    pub fn is_flipped(&self) -> bool {
+        matches!(
+            self,
+            Self::Compressed(_, true) | Self::CompressedSrgb(_, true)
+        )
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Self::Array(_) | Self::SrgbArray(_))
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Linear(texture) => texture.dimensions(),
+            Self::Srgb(texture) => texture.dimensions(),
+            Self::Compressed(texture, _) => texture.dimensions(),
+            Self::CompressedSrgb(texture, _) => texture.dimensions(),
+            Self::Array(texture) => texture.dimensions(),
+            Self::SrgbArray(texture) => texture.dimensions(),
+        }
+    }
+
+    pub fn get_framebuffer<'a>(
+        &'a self,
+        display: &Display<WindowSurface>,
+    ) -> SimpleFrameBuffer<'a> {
+        match self {
+            Self::Linear(texture) => SimpleFrameBuffer::new(display, texture),
+            Self::Srgb(texture) => SimpleFrameBuffer::new(display, texture),
+            _ => panic!("Only uncompressed 2D textures can be rendered into"),
+        }
+        .unwrap()
+    }
+
+    fn get_mipmap_levels(&self) -> u32 {
+        match self {
+            Self::Linear(texture) => texture.get_mipmap_levels(),
+            Self::Srgb(texture) => texture.get_mipmap_levels(),
+            Self::Compressed(texture, _) => texture.get_mipmap_levels(),
+            Self::CompressedSrgb(texture, _) => texture.get_mipmap_levels(),
+            Self::Array(texture) => texture.get_mipmap_levels(),
+            Self::SrgbArray(texture) => texture.get_mipmap_levels(),
+        }
+    }
+
+    pub fn sampled(&self, settings: &TextureSettings) -> TextureUniform<'_> {
+        let wrap = match settings.wrap {
+            WrapMode::Repeat => SamplerWrapFunction::Repeat,
+            WrapMode::MirroredRepeat => SamplerWrapFunction::Mirror,
+            WrapMode::Clamp => SamplerWrapFunction::Clamp,
+        };
+
+        // Textures without mipmaps, like render targets, would be incomplete with a mipmap filter
+        let mipmap_filter = settings
+            .mipmap_filter
+            .filter(|_| self.get_mipmap_levels() > 1);
+
+        let behavior = SamplerBehavior {
+            wrap_function: (wrap, wrap, wrap),
+            minify_filter: match (settings.min_filter, mipmap_filter) {
+                (FilterMode::Nearest, None) => MinifySamplerFilter::Nearest,
+                (FilterMode::Linear, None) => MinifySamplerFilter::Linear,
+                (FilterMode::Nearest, Some(FilterMode::Nearest)) => {
+                    MinifySamplerFilter::NearestMipmapNearest
+                }
+                (FilterMode::Linear, Some(FilterMode::Nearest)) => {
+                    MinifySamplerFilter::LinearMipmapNearest
+                }
+                (FilterMode::Nearest, Some(FilterMode::Linear)) => {
+                    MinifySamplerFilter::NearestMipmapLinear
+                }
+                (FilterMode::Linear, Some(FilterMode::Linear)) => {
+                    MinifySamplerFilter::LinearMipmapLinear
+                }
+            },
+            magnify_filter: match settings.mag_filter {
+                FilterMode::Nearest => MagnifySamplerFilter::Nearest,
+                FilterMode::Linear => MagnifySamplerFilter::Linear,
+            },
+            max_anisotropy: settings.anisotropy.max(1),
+            ..Default::default()
+        };
+
+        TextureUniform(match self {
+            Self::Linear(texture) => UniformValue::Texture2d(texture, Some(behavior)),
+            Self::Srgb(texture) => UniformValue::SrgbTexture2d(texture, Some(behavior)),
+            Self::Compressed(texture, _) => {
+                UniformValue::CompressedTexture2d(texture, Some(behavior))
+            }
+            Self::CompressedSrgb(texture, _) => {
+                UniformValue::CompressedSrgbTexture2d(texture, Some(behavior))
+            }
+            Self::Array(texture) => UniformValue::Texture2dArray(texture, Some(behavior)),
+            Self::SrgbArray(texture) => UniformValue::SrgbTexture2dArray(texture, Some(behavior)),
+        })
+    }
+}
+
+fn level_rect(width: u32, height: u32, level: usize) -> (Rect, u32, u32) {
+    let width = (width >> level).max(1);
+    let height = (height >> level).max(1);
+
+    (
+        Rect {
+            left: 0,
+            bottom: 0,
+            width,
+            height,
+        },
+        width,
+        height,
+    )
+}
+
+pub struct TextureUniform<'a>(UniformValue<'a>);
+
+impl AsUniformValue for TextureUniform<'_> {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        self.0
+    }
+}
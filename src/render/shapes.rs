@@ -0,0 +1,653 @@
+use std::{
+    collections::HashMap,
+    f32::consts::{FRAC_PI_2, PI, TAU},
+};
+
+use glam::{Vec2, Vec3};
+
+use super::{
+    mesh_data::{MeshData, MeshVertex},
+    Mesh,
+};
+
+// Segment, ring and side counts are clamped to at most this, subdivisions of planes to one
+// less, so a bad value can't ask for billions of vertices
+const MAX_SEGMENTS: u32 = 1024;
+// Every subdivision makes four times as many faces
+const MAX_ICOSPHERE_SUBDIVISIONS: u32 = 6;
+
+// A point on the outline that gets spun around the Y axis. The normal is in the same
+// radius/height plane and v runs down the outline.
+#[derive(Clone, Copy)]
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    normal: Vec2,
+    v: f32,
+}
+
+#[derive(Default)]
+struct ShapeBuilder {
+    vertices: Vec<MeshVertex>,
+    indices: Vec<u32>,
+}
+
+impl ShapeBuilder {
+    fn vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) -> u32 {
+        self.vertices.push(MeshVertex {
+            position,
+            normal,
+            tex_coords: uv,
+        });
+        self.vertices.len() as u32 - 1
+    }
+
+    // The profile has to go from top to bottom on the outward facing side
+    fn lathe(&mut self, profile: &[ProfilePoint], segments: u32) {
+        let start = self.vertices.len() as u32;
+
+        for point in profile {
+            // The seam is duplicated so it can have both u = 0 and u = 1
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin, cos) = (u * TAU).sin_cos();
+
+                self.vertex(
+                    Vec3::new(point.radius * cos, point.y, -point.radius * sin),
+                    Vec3::new(point.normal.x * cos, point.normal.y, -point.normal.x * sin)
+                        .normalize(),
+                    Vec2::new(u, point.v),
+                );
+            }
+        }
+
+        // Points on the axis, like the poles of a sphere, only get the triangle that has area
+        let largest = profile
+            .iter()
+            .map(|point| point.radius.abs())
+            .fold(0.0, f32::max);
+        let on_axis = |point: &ProfilePoint| point.radius.abs() <= largest * 1e-6;
+
+        let stride = segments + 1;
+        for (ring, points) in profile.windows(2).enumerate() {
+            for segment in 0..segments {
+                let a = start + ring as u32 * stride + segment;
+                let b = a + stride;
+
+                if !on_axis(&points[0]) {
+                    self.indices.extend([a, b, a + 1]);
+                }
+                if !on_axis(&points[1]) {
+                    self.indices.extend([b, b + 1, a + 1]);
+                }
+            }
+        }
+    }
+
+    fn disc(&mut self, y: f32, radius: f32, segments: u32, up: bool) {
+        let normal = if up { Vec3::Y } else { Vec3::NEG_Y };
+        let center = self.vertex(Vec3::new(0.0, y, 0.0), normal, Vec2::splat(0.5));
+
+        for segment in 0..=segments {
+            let (sin, cos) = (segment as f32 / segments as f32 * TAU).sin_cos();
+            // Mirrored underneath so the texture isn't flipped when seen from below
+            let v = if up { 0.5 + 0.5 * sin } else { 0.5 - 0.5 * sin };
+
+            self.vertex(
+                Vec3::new(radius * cos, y, -radius * sin),
+                normal,
+                Vec2::new(0.5 + 0.5 * cos, v),
+            );
+        }
+
+        for segment in 0..segments {
+            let a = center + 1 + segment;
+
+            if up {
+                self.indices.extend([center, a, a + 1]);
+            } else {
+                self.indices.extend([center, a + 1, a]);
+            }
+        }
+    }
+}
+
+// Generates the built-in and procedural meshes, None for everything else
+pub fn generate(mesh: &Mesh) -> Option<MeshData> {
+    let mut builder = ShapeBuilder::default();
+
+    match *mesh {
+        Mesh::Triangle => {
+            let vertices = vec![
+                MeshVertex::new(-0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0),
+                MeshVertex::new(0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0),
+                MeshVertex::new(0.0, 0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0),
+            ];
+
+            return Some(built_in(vertices, None));
+        }
+        Mesh::Square => {
+            let vertices = vec![
+                MeshVertex::new(0.5, 0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0),
+                MeshVertex::new(0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0),
+                MeshVertex::new(-0.5, -0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0),
+                MeshVertex::new(-0.5, 0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0),
+            ];
+
+            let indices = vec![0, 1, 3, 1, 2, 3];
+
+            return Some(built_in(vertices, Some(indices)));
+        }
+        Mesh::Cube => {
+            let vertices = vec![
+                MeshVertex::new(-0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 0.0),
+                MeshVertex::new(0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 0.0),
+                MeshVertex::new(0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 1.0),
+                MeshVertex::new(0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 1.0),
+                MeshVertex::new(-0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 1.0),
+                MeshVertex::new(-0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 0.0),
+                MeshVertex::new(-0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 0.0),
+                MeshVertex::new(0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 0.0),
+                MeshVertex::new(0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 1.0),
+                MeshVertex::new(0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 1.0),
+                MeshVertex::new(-0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 1.0),
+                MeshVertex::new(-0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 0.0),
+                MeshVertex::new(-0.5, 0.5, 0.5, -1.0, 0.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(-0.5, 0.5, -0.5, -1.0, 0.0, 0.0, 1.0, 1.0),
+                MeshVertex::new(-0.5, -0.5, -0.5, -1.0, 0.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(-0.5, -0.5, -0.5, -1.0, 0.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(-0.5, -0.5, 0.5, -1.0, 0.0, 0.0, 0.0, 0.0),
+                MeshVertex::new(-0.5, 0.5, 0.5, -1.0, 0.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(0.5, 0.5, -0.5, 1.0, 0.0, 0.0, 1.0, 1.0),
+                MeshVertex::new(0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(0.5, -0.5, 0.5, 1.0, 0.0, 0.0, 0.0, 0.0),
+                MeshVertex::new(0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(-0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 1.0, 1.0),
+                MeshVertex::new(0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(-0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 0.0, 0.0),
+                MeshVertex::new(-0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(-0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.0, 1.0),
+                MeshVertex::new(0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 1.0, 1.0),
+                MeshVertex::new(0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 1.0, 0.0),
+                MeshVertex::new(-0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 0.0, 0.0),
+                MeshVertex::new(-0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.0, 1.0),
+            ];
+
+            return Some(built_in(vertices, None));
+        }
+        Mesh::UvSphere {
+            radius,
+            segments,
+            rings,
+        } => {
+            let rings = rings.clamp(2, MAX_SEGMENTS);
+            let profile: Vec<_> = (0..=rings)
+                .map(|ring| {
+                    let v = ring as f32 / rings as f32;
+                    let (sin, cos) = (v * PI).sin_cos();
+
+                    ProfilePoint {
+                        radius: radius.0 * sin,
+                        y: radius.0 * cos,
+                        normal: Vec2::new(sin, cos),
+                        v: 1.0 - v,
+                    }
+                })
+                .collect();
+
+            builder.lathe(&profile, segments.clamp(3, MAX_SEGMENTS));
+        }
+        Mesh::Icosphere {
+            radius,
+            subdivisions,
+        } => icosphere(
+            &mut builder,
+            radius.0,
+            subdivisions.min(MAX_ICOSPHERE_SUBDIVISIONS),
+        ),
+        Mesh::Plane {
+            width,
+            depth,
+            subdivisions,
+        } => {
+            let cells = subdivisions.min(MAX_SEGMENTS - 1) + 1;
+
+            for z in 0..=cells {
+                for x in 0..=cells {
+                    let uv = Vec2::new(x as f32, z as f32) / cells as f32;
+
+                    builder.vertex(
+                        Vec3::new((uv.x - 0.5) * width.0, 0.0, (0.5 - uv.y) * depth.0),
+                        Vec3::Y,
+                        uv,
+                    );
+                }
+            }
+
+            for z in 0..cells {
+                for x in 0..cells {
+                    let a = z * (cells + 1) + x;
+                    let b = a + cells + 1;
+
+                    builder.indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+                }
+            }
+        }
+        Mesh::Cylinder {
+            radius,
+            height,
+            segments,
+        } => {
+            let segments = segments.clamp(3, MAX_SEGMENTS);
+            let half = height.0 * 0.5;
+
+            builder.lathe(
+                &[
+                    ProfilePoint {
+                        radius: radius.0,
+                        y: half,
+                        normal: Vec2::X,
+                        v: 1.0,
+                    },
+                    ProfilePoint {
+                        radius: radius.0,
+                        y: -half,
+                        normal: Vec2::X,
+                        v: 0.0,
+                    },
+                ],
+                segments,
+            );
+            builder.disc(half, radius.0, segments, true);
+            builder.disc(-half, radius.0, segments, false);
+        }
+        Mesh::Cone {
+            radius,
+            height,
+            segments,
+        } => {
+            let segments = segments.clamp(3, MAX_SEGMENTS);
+            let half = height.0 * 0.5;
+            let normal = Vec2::new(height.0, radius.0).normalize();
+
+            builder.lathe(
+                &[
+                    ProfilePoint {
+                        radius: 0.0,
+                        y: half,
+                        normal,
+                        v: 1.0,
+                    },
+                    ProfilePoint {
+                        radius: radius.0,
+                        y: -half,
+                        normal,
+                        v: 0.0,
+                    },
+                ],
+                segments,
+            );
+            builder.disc(-half, radius.0, segments, false);
+        }
+        Mesh::Capsule {
+            radius,
+            height,
+            segments,
+            rings,
+        } => {
+            let rings = rings.clamp(1, MAX_SEGMENTS);
+            let half = height.0 * 0.5;
+            // v follows the length along the outline so the texture isn't stretched on the caps
+            let length = height.0 + PI * radius.0;
+
+            let cap = |top: bool| {
+                (0..=rings).map(move |ring| {
+                    let angle = ring as f32 / rings as f32 * FRAC_PI_2;
+                    let angle = if top { angle } else { angle + FRAC_PI_2 };
+                    let (sin, cos) = angle.sin_cos();
+                    let center = if top { half } else { -half };
+                    let distance = angle * radius.0 + if top { 0.0 } else { height.0 };
+
+                    ProfilePoint {
+                        radius: radius.0 * sin,
+                        y: center + radius.0 * cos,
+                        normal: Vec2::new(sin, cos),
+                        v: 1.0 - distance / length,
+                    }
+                })
+            };
+            let profile: Vec<_> = cap(true).chain(cap(false)).collect();
+
+            builder.lathe(&profile, segments.clamp(3, MAX_SEGMENTS));
+        }
+        Mesh::Torus {
+            radius,
+            tube_radius,
+            segments,
+            sides,
+        } => {
+            let sides = sides.clamp(3, MAX_SEGMENTS);
+            let profile: Vec<_> = (0..=sides)
+                .map(|side| {
+                    let v = side as f32 / sides as f32;
+                    // Clockwise, so the outer side is walked top to bottom
+                    let (sin, cos) = (-v * TAU).sin_cos();
+
+                    ProfilePoint {
+                        radius: radius.0 + tube_radius.0 * cos,
+                        y: tube_radius.0 * sin,
+                        normal: Vec2::new(cos, sin),
+                        v: 1.0 - v,
+                    }
+                })
+                .collect();
+
+            builder.lathe(&profile, segments.clamp(3, MAX_SEGMENTS));
+        }
+        _ => return None,
+    }
+
+    let mut data = MeshData::new(builder.vertices, Some(builder.indices));
+    data.generate_tangents();
+
+    Some(data)
+}
+
+fn built_in(vertices: Vec<MeshVertex>, indices: Option<Vec<u32>>) -> MeshData {
+    let mut data = MeshData::new(vertices, indices);
+    data.generate_tangents();
+    data
+}
+
+fn icosphere(builder: &mut ShapeBuilder, radius: f32, subdivisions: u32) {
+    let t = (1.0 + 5.0_f32.sqrt()) * 0.5;
+    let mut points: Vec<Vec3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .into_iter()
+    .map(|(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+
+    let mut faces = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: usize, b: usize| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push(((points[a] + points[b]) * 0.5).normalize());
+                points.len() - 1
+            })
+        };
+
+        faces = faces
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let uv = |point: Vec3| {
+        Vec2::new(
+            0.5 + point.z.atan2(point.x) / -TAU,
+            0.5 + point.y.asin() / PI,
+        )
+    };
+
+    // Faces crossing the seam or touching a pole get their own vertices, with UVs fixed up
+    // so they don't wrap around the whole texture
+    let mut shared = HashMap::new();
+
+    for face in faces {
+        let mut uvs = face.map(|i| uv(points[i]));
+        let wraps = uvs.iter().any(|uv| uv.x > 0.75) && uvs.iter().any(|uv| uv.x < 0.25);
+        if wraps {
+            for uv in &mut uvs {
+                if uv.x < 0.5 {
+                    uv.x += 1.0;
+                }
+            }
+        }
+
+        let poles: Vec<_> = face.map(|i| points[i].y.abs() > 0.9999).to_vec();
+        if poles.iter().any(|pole| *pole) {
+            let u = (0..3).filter(|i| !poles[*i]).map(|i| uvs[i].x).sum::<f32>() / 2.0;
+            for (uv, pole) in uvs.iter_mut().zip(&poles) {
+                if *pole {
+                    uv.x = u;
+                }
+            }
+        }
+
+        for (i, uv) in face.into_iter().zip(uvs) {
+            let point = points[i];
+            let index = if wraps || poles.contains(&true) {
+                builder.vertex(point * radius, point, uv)
+            } else {
+                *shared
+                    .entry(i)
+                    .or_insert_with(|| builder.vertex(point * radius, point, uv))
+            };
+
+            builder.indices.push(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::render::Dimension;
+
+    // Checks the counts, that every normal points away from the given center of the surface
+    // around it and that triangles wind counter-clockwise seen from outside. Copies of
+    // vertices made for different tangents aren't counted.
+    fn check_shape(
+        mesh: Mesh,
+        vertices: Option<usize>,
+        indices: usize,
+        center: fn(Vec3) -> Vec3,
+    ) -> MeshData {
+        let data = generate(&mesh).unwrap();
+        let normals = data.normals.as_ref().unwrap();
+        let tex_coords = data.tex_coords.as_ref().unwrap();
+        let triangles = data.indices.as_ref().unwrap();
+
+        let distinct: HashSet<_> = (0..data.positions.len())
+            .map(|i| {
+                let [x, y, z] = data.positions[i].to_array();
+                let [nx, ny, nz] = normals[i].to_array();
+                let [u, v] = tex_coords[i].to_array();
+                [x, y, z, nx, ny, nz, u, v].map(f32::to_bits)
+            })
+            .collect();
+        if let Some(vertices) = vertices {
+            assert_eq!(distinct.len(), vertices, "{mesh:?}");
+        }
+        assert_eq!(triangles.len(), indices, "{mesh:?}");
+
+        for (position, normal) in data.positions.iter().zip(normals) {
+            assert!(
+                normal.dot(*position - center(*position)) > 0.0,
+                "{mesh:?} has an inward normal at {position}"
+            );
+        }
+
+        for triangle in triangles.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| data.positions[i]);
+            let face = (pb - pa).cross(pc - pa);
+            assert!(
+                face.dot(normals[a] + normals[b] + normals[c]) > 0.0,
+                "{mesh:?} has a triangle facing inwards or without area at {pa}"
+            );
+        }
+
+        data
+    }
+
+    #[test]
+    fn shapes_face_outwards() {
+        let origin = |_| Vec3::ZERO;
+        let radius = Dimension(1.0);
+        let height = Dimension(2.0);
+
+        check_shape(
+            Mesh::UvSphere {
+                radius,
+                segments: 8,
+                rings: 4,
+            },
+            Some(5 * 9),
+            (4 * 8 * 2 - 2 * 8) * 3,
+            origin,
+        );
+        // Faces on the seam and the poles get their own vertices, but the positions are shared
+        let icosphere = check_shape(
+            Mesh::Icosphere {
+                radius,
+                subdivisions: 1,
+            },
+            None,
+            20 * 4 * 3,
+            origin,
+        );
+        let positions: HashSet<_> = icosphere
+            .positions
+            .iter()
+            .map(|position| position.to_array().map(f32::to_bits))
+            .collect();
+        assert_eq!(positions.len(), 10 * 4 + 2);
+        check_shape(
+            Mesh::Plane {
+                width: height,
+                depth: height,
+                subdivisions: 2,
+            },
+            Some(4 * 4),
+            3 * 3 * 6,
+            |position| position - Vec3::Y,
+        );
+        check_shape(
+            Mesh::Cylinder {
+                radius,
+                height,
+                segments: 8,
+            },
+            Some(2 * 9 + 2 * 10),
+            8 * 6 + 2 * 8 * 3,
+            origin,
+        );
+        check_shape(
+            Mesh::Cone {
+                radius,
+                height,
+                segments: 8,
+            },
+            Some(2 * 9 + 10),
+            8 * 3 + 8 * 3,
+            origin,
+        );
+        check_shape(
+            Mesh::Capsule {
+                radius,
+                height,
+                segments: 8,
+                rings: 2,
+            },
+            Some(6 * 9),
+            (5 * 8 * 2 - 2 * 8) * 3,
+            origin,
+        );
+        check_shape(
+            Mesh::Torus {
+                radius: height,
+                tube_radius: radius,
+                segments: 8,
+                sides: 6,
+            },
+            Some(7 * 9),
+            6 * 8 * 6,
+            |position| (position * Vec3::new(1.0, 0.0, 1.0)).normalize() * 2.0,
+        );
+    }
+
+    #[test]
+    fn counts_are_clamped() {
+        let radius = Dimension(1.0);
+
+        // Too few to close the shape, and far too many
+        check_shape(
+            Mesh::UvSphere {
+                radius,
+                segments: 0,
+                rings: u32::MAX,
+            },
+            Some((MAX_SEGMENTS as usize + 1) * 4),
+            (MAX_SEGMENTS as usize * 3 * 2 - 2 * 3) * 3,
+            |_| Vec3::ZERO,
+        );
+        check_shape(
+            Mesh::Torus {
+                radius: Dimension(2.0),
+                tube_radius: radius,
+                segments: 1,
+                sides: 0,
+            },
+            Some(4 * 4),
+            3 * 3 * 6,
+            |position| (position * Vec3::new(1.0, 0.0, 1.0)).normalize() * 2.0,
+        );
+        assert_eq!(
+            generate(&Mesh::Icosphere {
+                radius,
+                subdivisions: u32::MAX,
+            })
+            .unwrap()
+            .indices
+            .unwrap()
+            .len(),
+            20 * 4_usize.pow(MAX_ICOSPHERE_SUBDIVISIONS) * 3
+        );
+    }
+}
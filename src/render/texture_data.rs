@@ -0,0 +1,273 @@
+use std::{fs, path::Path};
+
+use ddsfile::{Dds, DxgiFormat};
+use image::DynamicImage;
+use ktx2::Format;
+
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const DDS_MAGIC: [u8; 4] = *b"DDS ";
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BlockFormat {
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4,
+    Bc5,
+    Bc6hUnsigned,
+    Bc6hSigned,
+    Bc7,
+}
+
+impl BlockFormat {
+    pub fn get_block_size(&self) -> usize {
+        match self {
+            Self::Bc1 | Self::Bc4 => 8,
+            _ => 16,
+        }
+    }
+
+    pub fn get_level_size(&self, width: u32, height: u32) -> usize {
+        (width.div_ceil(4) * height.div_ceil(4)) as usize * self.get_block_size()
+    }
+}
+
+// Decoded images are stored bottom row first, like OpenGL expects. Compressed blocks can't
+// all be flipped without re-encoding, so they are uploaded as stored and sampled flipped.
+pub enum TextureData {
+    Rgba8 {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    },
+    RgbaF32 {
+        width: u32,
+        height: u32,
+        data: Vec<f32>,
+    },
+    Compressed {
+        format: BlockFormat,
+        // Only set when the file itself says so, otherwise the texture settings decide
+        srgb: bool,
+        // Set when the rows are stored top first, which is the usual case
+        flip_v: bool,
+        width: u32,
+        height: u32,
+        levels: Vec<Vec<u8>>,
+    },
+}
+
+impl TextureData {
+    // The format is detected from the contents, not the extension. Files that can't be read
+    // or aren't supported are warned about and replaced with a blank texture.
+    pub fn load(path: &Path) -> Self {
+        Self::try_load(path).unwrap_or_else(|error| {
+            eprintln!("Warning: can't load texture {path:?}, using a blank one instead: {error}");
+            Self::blank()
+        })
+    }
+
+    fn try_load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|error| error.to_string())?;
+
+        if bytes.starts_with(&KTX2_MAGIC) {
+            Self::from_ktx2(&bytes)
+        } else if bytes.starts_with(&DDS_MAGIC) {
+            Self::from_dds(&bytes)
+        } else {
+            let image = image::load_from_memory(&bytes).map_err(|error| error.to_string())?;
+            Ok(Self::from_image(image))
+        }
+    }
+
+    // A single white pixel
+    pub fn blank() -> Self {
+        Self::Rgba8 {
+            width: 1,
+            height: 1,
+            data: vec![255; 4],
+        }
+    }
+
+    pub fn from_image(image: DynamicImage) -> Self {
+        let image = image.flipv();
+        let (width, height) = (image.width(), image.height());
+
+        match image {
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => Self::RgbaF32 {
+                width,
+                height,
+                data: image.into_rgba32f().into_raw(),
+            },
+            _ => Self::Rgba8 {
+                width,
+                height,
+                data: image.into_rgba8().into_raw(),
+            },
+        }
+    }
+
+    fn from_ktx2(bytes: &[u8]) -> Result<Self, String> {
+        let reader = ktx2::Reader::new(bytes).map_err(|error| error.to_string())?;
+        let header = reader.header();
+
+        if header.supercompression_scheme.is_some() {
+            return Err("supercompressed KTX2 textures aren't supported".into());
+        }
+
+        let (format, srgb) = match header.format {
+            Some(Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK) => {
+                (BlockFormat::Bc1, false)
+            }
+            Some(Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK) => {
+                (BlockFormat::Bc1, true)
+            }
+            Some(Format::BC2_UNORM_BLOCK) => (BlockFormat::Bc2, false),
+            Some(Format::BC2_SRGB_BLOCK) => (BlockFormat::Bc2, true),
+            Some(Format::BC3_UNORM_BLOCK) => (BlockFormat::Bc3, false),
+            Some(Format::BC3_SRGB_BLOCK) => (BlockFormat::Bc3, true),
+            Some(Format::BC4_UNORM_BLOCK) => (BlockFormat::Bc4, false),
+            Some(Format::BC5_UNORM_BLOCK) => (BlockFormat::Bc5, false),
+            Some(Format::BC6H_UFLOAT_BLOCK) => (BlockFormat::Bc6hUnsigned, false),
+            Some(Format::BC6H_SFLOAT_BLOCK) => (BlockFormat::Bc6hSigned, false),
+            Some(Format::BC7_UNORM_BLOCK) => (BlockFormat::Bc7, false),
+            Some(Format::BC7_SRGB_BLOCK) => (BlockFormat::Bc7, true),
+            format => return Err(format!("unsupported KTX2 format {format:?}")),
+        };
+
+        let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+        // Right and down unless the file says otherwise
+        let flip_v = reader
+            .key_value_data()
+            .find(|(key, _)| *key == "KTXorientation")
+            .map_or(true, |(_, value)| value.get(1) != Some(&b'u'));
+
+        // Only the first layer and face of each level is used
+        let levels = reader
+            .levels()
+            .enumerate()
+            .map(|(i, level)| {
+                let size = format.get_level_size((width >> i).max(1), (height >> i).max(1));
+                level.data.get(..size).map(<[u8]>::to_vec)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or("a KTX2 level is smaller than its size")?;
+
+        Self::compressed(format, srgb, flip_v, width, height, levels)
+    }
+
+    fn from_dds(bytes: &[u8]) -> Result<Self, String> {
+        let dds = Dds::read(bytes).map_err(|error| error.to_string())?;
+
+        let (format, srgb) = match dds.get_dxgi_format() {
+            Some(DxgiFormat::BC1_Typeless | DxgiFormat::BC1_UNorm) => (BlockFormat::Bc1, false),
+            Some(DxgiFormat::BC1_UNorm_sRGB) => (BlockFormat::Bc1, true),
+            Some(DxgiFormat::BC2_Typeless | DxgiFormat::BC2_UNorm) => (BlockFormat::Bc2, false),
+            Some(DxgiFormat::BC2_UNorm_sRGB) => (BlockFormat::Bc2, true),
+            Some(DxgiFormat::BC3_Typeless | DxgiFormat::BC3_UNorm) => (BlockFormat::Bc3, false),
+            Some(DxgiFormat::BC3_UNorm_sRGB) => (BlockFormat::Bc3, true),
+            Some(DxgiFormat::BC4_Typeless | DxgiFormat::BC4_UNorm) => (BlockFormat::Bc4, false),
+            Some(DxgiFormat::BC5_Typeless | DxgiFormat::BC5_UNorm) => (BlockFormat::Bc5, false),
+            Some(DxgiFormat::BC6H_Typeless | DxgiFormat::BC6H_UF16) => {
+                (BlockFormat::Bc6hUnsigned, false)
+            }
+            Some(DxgiFormat::BC6H_SF16) => (BlockFormat::Bc6hSigned, false),
+            Some(DxgiFormat::BC7_Typeless | DxgiFormat::BC7_UNorm) => (BlockFormat::Bc7, false),
+            Some(DxgiFormat::BC7_UNorm_sRGB) => (BlockFormat::Bc7, true),
+            format => return Err(format!("unsupported DDS format {format:?}")),
+        };
+
+        // Legacy headers get reported as sRGB without actually saying anything about it
+        let srgb = srgb && dds.header10.is_some();
+
+        let (width, height) = (dds.get_width(), dds.get_height());
+        let mut data = dds.get_data(0).map_err(|error| error.to_string())?;
+        let mut levels = Vec::new();
+
+        for i in 0..dds.get_num_mipmap_levels().max(1) {
+            let size = format.get_level_size((width >> i).max(1), (height >> i).max(1));
+            if data.len() < size {
+                return Err("the DDS data is smaller than its levels".into());
+            }
+            let (level, rest) = data.split_at(size);
+
+            levels.push(level.to_vec());
+            data = rest;
+        }
+
+        Self::compressed(format, srgb, true, width, height, levels)
+    }
+
+    fn compressed(
+        format: BlockFormat,
+        srgb: bool,
+        flip_v: bool,
+        width: u32,
+        height: u32,
+        levels: Vec<Vec<u8>>,
+    ) -> Result<Self, String> {
+        if width == 0 || height == 0 || levels.is_empty() {
+            return Err(format!(
+                "the texture is {width}x{height} with {} levels",
+                levels.len()
+            ));
+        }
+
+        Ok(Self::Compressed {
+            format,
+            srgb,
+            flip_v,
+            width,
+            height,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_blank(data: TextureData) {
+        let TextureData::Rgba8 {
+            width,
+            height,
+            data,
+        } = data
+        else {
+            panic!("Not a blank texture");
+        };
+        assert_eq!((width, height, data), (1, 1, vec![255; 4]));
+    }
+
+    #[test]
+    fn broken_files_are_blank() {
+        let directory = std::env::temp_dir().join("broken_files_are_blank");
+        fs::create_dir_all(&directory).unwrap();
+
+        assert_blank(TextureData::load(&directory.join("missing.png")));
+
+        for (name, bytes) in [
+            ("garbage.png", b"not an image".to_vec()),
+            ("truncated.dds", DDS_MAGIC.to_vec()),
+            ("truncated.ktx2", KTX2_MAGIC.to_vec()),
+        ] {
+            let path = directory.join(name);
+            fs::write(&path, bytes).unwrap();
+            assert_blank(TextureData::load(&path));
+        }
+    }
+
+    #[test]
+    fn compressed_without_levels() {
+        assert!(TextureData::compressed(BlockFormat::Bc1, false, true, 4, 4, Vec::new()).is_err());
+        assert!(
+            TextureData::compressed(BlockFormat::Bc1, false, true, 0, 4, vec![vec![0; 8]]).is_err()
+        );
+        assert!(
+            TextureData::compressed(BlockFormat::Bc1, false, true, 4, 4, vec![vec![0; 8]]).is_ok()
+        );
+    }
+}
@@ -0,0 +1,22 @@
+// Can be changed at runtime, the renderer rebuilds its buffers on the next frame
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct AntiAliasingResource {
+    // 0 or 1 disables MSAA, otherwise usually 2, 4 or 8
+    pub msaa_samples: u32,
+    pub fxaa: bool,
+}
+
+impl Default for AntiAliasingResource {
+    fn default() -> Self {
+        Self {
+            msaa_samples: 4,
+            fxaa: false,
+        }
+    }
+}
+
+impl AntiAliasingResource {
+    pub fn get_msaa_samples(&self) -> Option<u32> {
+        (self.msaa_samples > 1).then_some(self.msaa_samples)
+    }
+}
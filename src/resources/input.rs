@@ -1,78 +1,246 @@
+use std::{collections::HashMap, time::Duration};
+
 use bitvec::prelude::*;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
 use winit::{
-    dpi::PhysicalPosition,
-    event::{DeviceEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceEvent, ElementState, Ime, MouseButton, MouseScrollDelta, WindowEvent},
     keyboard::{KeyCode, PhysicalKey},
 };
 
+use crate::{
+    gamepad::{
+        apply_dead_zone, GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent,
+        GamepadEventKind, GamepadId, GamepadState, GamepadStick,
+    },
+    recording::InputRecording,
+};
+
+const KEY_COUNT: usize = 256;
+const MOUSE_BUTTON_COUNT: usize = 8;
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+const DEFAULT_STICK_DEAD_ZONE: f32 = 0.15;
+const DEFAULT_TRIGGER_DEAD_ZONE: f32 = 0.05;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum InputEvent {
+    Focused(bool),
+    Resized(u32, u32),
+    Key(KeyCode, ElementState),
+    MouseButton(MouseButton, ElementState),
+    CursorMoved(f32, f32),
+    Scroll(f32, f32),
+    MouseMotion(f32, f32),
+    Gamepad(GamepadEvent),
+    Text(String),
+    Ime(Ime),
+}
+
 pub struct InputResource {
     focused: bool,
+    window_size: PhysicalSize<u32>,
     cursor_pos: PhysicalPosition<f32>,
     scroll_delta: PhysicalPosition<f32>,
     mouse_delta: PhysicalPosition<f32>,
     pressed_keys: BitArray<[usize; 4], Lsb0>,
+    just_pressed_keys: BitArray<[usize; 4], Lsb0>,
+    just_released_keys: BitArray<[usize; 4], Lsb0>,
+    key_held_durations: [Duration; KEY_COUNT],
     pressed_mouse_buttons: BitArray<[u8; 1], Lsb0>,
+    just_pressed_mouse_buttons: BitArray<[u8; 1], Lsb0>,
+    just_released_mouse_buttons: BitArray<[u8; 1], Lsb0>,
+    mouse_button_held_durations: [Duration; MOUSE_BUTTON_COUNT],
+    gamepads: HashMap<GamepadId, GamepadState>,
+    stick_dead_zone: f32,
+    trigger_dead_zone: f32,
+    recording: Option<InputRecording>,
+    text_input_active: bool,
+    text: String,
+    preedit: Option<(String, Option<(usize, usize)>)>,
+    ime_cursor_area: Option<(PhysicalPosition<f32>, PhysicalSize<f32>)>,
 }
 
 impl InputResource {
-    pub fn new(focused: bool) -> Self {
+    pub fn new(focused: bool, window_size: PhysicalSize<u32>) -> Self {
         Self {
             focused,
+            window_size,
             cursor_pos: PhysicalPosition { x: 0.0, y: 0.0 },
             scroll_delta: PhysicalPosition { x: 0.0, y: 0.0 },
             mouse_delta: PhysicalPosition { x: 0.0, y: 0.0 },
             pressed_keys: BitArray::new([0; 4]),
+            just_pressed_keys: BitArray::new([0; 4]),
+            just_released_keys: BitArray::new([0; 4]),
+            key_held_durations: [Duration::ZERO; KEY_COUNT],
             pressed_mouse_buttons: BitArray::new([0]),
+            just_pressed_mouse_buttons: BitArray::new([0]),
+            just_released_mouse_buttons: BitArray::new([0]),
+            mouse_button_held_durations: [Duration::ZERO; MOUSE_BUTTON_COUNT],
+            gamepads: HashMap::new(),
+            stick_dead_zone: DEFAULT_STICK_DEAD_ZONE,
+            trigger_dead_zone: DEFAULT_TRIGGER_DEAD_ZONE,
+            recording: None,
+            text_input_active: false,
+            text: String::new(),
+            preedit: None,
+            ime_cursor_area: None,
         }
     }
 
     pub fn device_event(&mut self, event: &DeviceEvent) {
-        if self.focused {
-            if let DeviceEvent::MouseMotion { delta } = event {
-                self.mouse_delta.x += delta.0 as f32;
-                self.mouse_delta.y += delta.1 as f32;
-            }
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.input_event(InputEvent::MouseMotion(delta.0 as f32, delta.1 as f32));
         }
     }
 
     pub fn window_event(&mut self, event: &WindowEvent) {
-        match event {
-            WindowEvent::Focused(is_focused) => self.focused = *is_focused,
-            WindowEvent::KeyboardInput { event, .. } => match event.state {
-                winit::event::ElementState::Pressed => {
-                    if let PhysicalKey::Code(code) = event.physical_key {
-                        self.pressed_keys.set(code as usize, true);
-                    }
+        let input_event = match event {
+            WindowEvent::Focused(is_focused) => InputEvent::Focused(*is_focused),
+            WindowEvent::Resized(size) => InputEvent::Resized(size.width, size.height),
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    self.input_event(InputEvent::Key(code, event.state));
                 }
-                winit::event::ElementState::Released => {
-                    if let PhysicalKey::Code(code) = event.physical_key {
-                        self.pressed_keys.set(code as usize, false);
+
+                match &event.text {
+                    Some(text) if self.text_input_active && event.state.is_pressed() => {
+                        InputEvent::Text(text.to_string())
                     }
+                    _ => return,
                 }
-            },
+            }
+            WindowEvent::Ime(ime) => InputEvent::Ime(ime.clone()),
             WindowEvent::CursorMoved { position, .. } => {
-                self.cursor_pos = physical_pos_cast(position)
+                InputEvent::CursorMoved(position.x as f32, position.y as f32)
+            }
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::PixelDelta(d) => InputEvent::Scroll(d.x as f32, d.y as f32),
+                MouseScrollDelta::LineDelta(x, y) => {
+                    InputEvent::Scroll(x * SCROLL_LINE_HEIGHT, y * SCROLL_LINE_HEIGHT)
+                }
+            },
+            WindowEvent::MouseInput { state, button, .. } => {
+                InputEvent::MouseButton(*button, *state)
+            }
+            _ => return,
+        };
+
+        self.input_event(input_event);
+    }
+
+    pub fn gamepad_event(&mut self, event: &GamepadEvent) {
+        self.input_event(InputEvent::Gamepad(*event));
+    }
+
+    pub fn poll_gamepads(&mut self, backend: &mut dyn GamepadBackend) {
+        while let Some(event) = backend.next_event() {
+            self.gamepad_event(&event);
+        }
+    }
+
+    pub fn input_event(&mut self, event: InputEvent) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(event.clone());
+        }
+
+        match event {
+            InputEvent::Focused(is_focused) => {
+                self.focused = is_focused;
+
+                // Release events for anything held while unfocused are never delivered
+                if !self.focused {
+                    self.release_all();
+                }
+            }
+            InputEvent::Resized(width, height) => {
+                self.window_size = PhysicalSize { width, height };
             }
-            WindowEvent::MouseWheel { delta, .. } => {
-                if let MouseScrollDelta::PixelDelta(d) = delta {
-                    self.scroll_delta.x += d.x as f32;
-                    self.scroll_delta.y += d.y as f32;
+            InputEvent::Key(code, state) => self.set_key(code, state),
+            InputEvent::MouseButton(button, state) => self.set_mouse_button(&button, state),
+            InputEvent::CursorMoved(x, y) => self.cursor_pos = PhysicalPosition { x, y },
+            InputEvent::Scroll(x, y) => {
+                self.scroll_delta.x += x;
+                self.scroll_delta.y += y;
+            }
+            InputEvent::MouseMotion(x, y) => {
+                if self.focused {
+                    self.mouse_delta.x += x;
+                    self.mouse_delta.y += y;
                 }
             }
-            WindowEvent::MouseInput { state, button, .. } => match state {
-                winit::event::ElementState::Pressed => {
-                    self.pressed_mouse_buttons
-                        .set(mouse_button_to_usize(button), true);
+            InputEvent::Text(text) => self.push_text(&text),
+            InputEvent::Ime(ime) => match ime {
+                Ime::Preedit(text, _) if text.is_empty() => self.preedit = None,
+                Ime::Preedit(text, cursor) => self.preedit = Some((text, cursor)),
+                Ime::Commit(text) => self.push_text(&text),
+                Ime::Enabled | Ime::Disabled => self.preedit = None,
+            },
+            InputEvent::Gamepad(event) => match event.kind {
+                GamepadEventKind::Connected => {
+                    self.gamepads.insert(event.id, GamepadState::new());
                 }
-                winit::event::ElementState::Released => {
-                    self.pressed_mouse_buttons
-                        .set(mouse_button_to_usize(button), false);
+                GamepadEventKind::Disconnected => {
+                    self.gamepads.remove(&event.id);
                 }
+                kind => self.gamepads.entry(event.id).or_default().event(&kind),
             },
-            _ => {}
         }
     }
 
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputRecording::new(self.window_size, self.focused));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        let mut recording = self.recording.take()?;
+        recording.finish();
+        Some(recording)
+    }
+
+    pub fn start_text_input(&mut self) {
+        self.text_input_active = true;
+    }
+
+    pub fn stop_text_input(&mut self) {
+        self.text_input_active = false;
+        self.text.clear();
+        self.preedit = None;
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.text_input_active
+    }
+
+    // Characters committed since the last tick, either typed directly or from the IME
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    // Text still being composed in the IME with the byte range of its cursor
+    pub fn get_preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        self.preedit
+            .as_ref()
+            .map(|(text, cursor)| (text.as_str(), *cursor))
+    }
+
+    // Where the text being edited is on screen, so the IME candidate box can be placed next to it
+    pub fn set_ime_cursor_area(&mut self, position: PhysicalPosition<f32>, size: PhysicalSize<f32>) {
+        self.ime_cursor_area = Some((position, size));
+    }
+
+    pub fn get_ime_cursor_area(&self) -> Option<(PhysicalPosition<f32>, PhysicalSize<f32>)> {
+        self.ime_cursor_area
+    }
+
+    pub fn get_window_size(&self) -> PhysicalSize<u32> {
+        self.window_size
+    }
+
+    pub fn get_cursor_pos(&self) -> PhysicalPosition<f32> {
+        self.cursor_pos
+    }
+
     pub fn get_mouse_delta(&self) -> PhysicalPosition<f32> {
         self.mouse_delta
     }
@@ -81,20 +249,204 @@ impl InputResource {
         self.scroll_delta
     }
 
+    pub fn get_scroll_lines(&self) -> PhysicalPosition<f32> {
+        PhysicalPosition {
+            x: self.scroll_delta.x / SCROLL_LINE_HEIGHT,
+            y: self.scroll_delta.y / SCROLL_LINE_HEIGHT,
+        }
+    }
+
     pub fn key_pressed(&self, key: KeyCode) -> bool {
         self.pressed_keys[key as usize]
     }
 
-    pub fn tick(&mut self) {
+    pub fn key_just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed_keys[key as usize]
+    }
+
+    pub fn key_just_released(&self, key: KeyCode) -> bool {
+        self.just_released_keys[key as usize]
+    }
+
+    // Zero once the key is released
+    pub fn key_held_duration(&self, key: KeyCode) -> Duration {
+        if self.key_pressed(key) {
+            self.key_held_durations[key as usize]
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_mouse_buttons[mouse_button_to_usize(&button)]
+    }
+
+    pub fn mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_mouse_buttons[mouse_button_to_usize(&button)]
+    }
+
+    pub fn mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.just_released_mouse_buttons[mouse_button_to_usize(&button)]
+    }
+
+    // Zero once the button is released
+    pub fn mouse_button_held_duration(&self, button: MouseButton) -> Duration {
+        if self.mouse_button_pressed(button) {
+            self.mouse_button_held_durations[mouse_button_to_usize(&button)]
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    pub fn set_gamepad_dead_zones(&mut self, stick: f32, trigger: f32) {
+        self.stick_dead_zone = stick;
+        self.trigger_dead_zone = trigger;
+    }
+
+    pub fn get_gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.keys().copied()
+    }
+
+    pub fn gamepad_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(&id)
+    }
+
+    pub fn gamepad_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.pressed[button as usize])
+    }
+
+    pub fn gamepad_button_just_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.just_pressed[button as usize])
+    }
+
+    pub fn gamepad_button_just_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.just_released[button as usize])
+    }
+
+    pub fn gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        let Some(gamepad) = self.gamepads.get(&id) else {
+            return 0.0;
+        };
+
+        match axis {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                apply_dead_zone(gamepad.axes[axis as usize], self.trigger_dead_zone)
+            }
+            _ => apply_dead_zone(gamepad.axes[axis as usize], self.stick_dead_zone),
+        }
+    }
+
+    // Radial dead zone, so diagonals aren't snapped to the axes like with per-axis dead zones
+    pub fn gamepad_stick(&self, id: GamepadId, stick: GamepadStick) -> Vec2 {
+        let Some(gamepad) = self.gamepads.get(&id) else {
+            return Vec2::ZERO;
+        };
+
+        let raw = match stick {
+            GamepadStick::Left => Vec2::new(
+                gamepad.axes[GamepadAxis::LeftStickX as usize],
+                gamepad.axes[GamepadAxis::LeftStickY as usize],
+            ),
+            GamepadStick::Right => Vec2::new(
+                gamepad.axes[GamepadAxis::RightStickX as usize],
+                gamepad.axes[GamepadAxis::RightStickY as usize],
+            ),
+        };
+
+        let length = raw.length().min(1.0);
+        if length <= self.stick_dead_zone {
+            Vec2::ZERO
+        } else {
+            raw.normalize() * apply_dead_zone(length, self.stick_dead_zone)
+        }
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
         self.scroll_delta = PhysicalPosition { x: 0.0, y: 0.0 };
         self.mouse_delta = PhysicalPosition { x: 0.0, y: 0.0 };
+
+        self.text.clear();
+
+        self.just_pressed_keys.fill(false);
+        self.just_released_keys.fill(false);
+        self.just_pressed_mouse_buttons.fill(false);
+        self.just_released_mouse_buttons.fill(false);
+
+        for i in self.pressed_keys.iter_ones() {
+            self.key_held_durations[i] += dt;
+        }
+        for i in self.pressed_mouse_buttons.iter_ones() {
+            self.mouse_button_held_durations[i] += dt;
+        }
+
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.tick();
+        }
+
+        if let Some(recording) = &mut self.recording {
+            recording.end_frame(dt);
+        }
+    }
+
+    fn set_key(&mut self, code: KeyCode, state: ElementState) {
+        let i = code as usize;
+
+        match state {
+            ElementState::Pressed => {
+                // Ignore key repeat
+                if !self.pressed_keys[i] {
+                    self.pressed_keys.set(i, true);
+                    self.just_pressed_keys.set(i, true);
+                    self.key_held_durations[i] = Duration::ZERO;
+                }
+            }
+            ElementState::Released => {
+                if self.pressed_keys[i] {
+                    self.pressed_keys.set(i, false);
+                    self.just_released_keys.set(i, true);
+                }
+            }
+        }
+    }
+
+    fn set_mouse_button(&mut self, button: &MouseButton, state: ElementState) {
+        let i = mouse_button_to_usize(button);
+
+        match state {
+            ElementState::Pressed => {
+                if !self.pressed_mouse_buttons[i] {
+                    self.pressed_mouse_buttons.set(i, true);
+                    self.just_pressed_mouse_buttons.set(i, true);
+                    self.mouse_button_held_durations[i] = Duration::ZERO;
+                }
+            }
+            ElementState::Released => {
+                if self.pressed_mouse_buttons[i] {
+                    self.pressed_mouse_buttons.set(i, false);
+                    self.just_released_mouse_buttons.set(i, true);
+                }
+            }
+        }
     }
-}
 
-fn physical_pos_cast(physical_pos: &PhysicalPosition<f64>) -> PhysicalPosition<f32> {
-    PhysicalPosition {
-        x: physical_pos.x as f32,
-        y: physical_pos.y as f32,
+    // Control characters like backspace and enter are left to the key events
+    fn push_text(&mut self, text: &str) {
+        if self.text_input_active {
+            self.text.extend(text.chars().filter(|c| !c.is_control()));
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.just_released_keys |= self.pressed_keys;
+        self.pressed_keys.fill(false);
+        self.just_released_mouse_buttons |= self.pressed_mouse_buttons;
+        self.pressed_mouse_buttons.fill(false);
     }
 }
 
@@ -108,3 +460,98 @@ fn mouse_button_to_usize(button: &MouseButton) -> usize {
         MouseButton::Other(_) => 6,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::mock_backend::MockGamepadBackend;
+
+    #[test]
+    fn mock_gamepad() {
+        let mut input = InputResource::new(true, PhysicalSize::new(800, 600));
+        let mut backend = MockGamepadBackend::new();
+        let id = GamepadId(3);
+
+        backend.connect(id);
+        backend.press(id, GamepadButton::South);
+        backend.set_axis(id, GamepadAxis::LeftStickX, 0.1);
+        backend.set_axis(id, GamepadAxis::LeftStickY, -0.1);
+        backend.set_axis(id, GamepadAxis::RightStickX, 0.575);
+        backend.set_axis(id, GamepadAxis::LeftTrigger, 0.04);
+        backend.set_axis(id, GamepadAxis::RightTrigger, 1.0);
+        input.poll_gamepads(&mut backend);
+
+        assert!(input.gamepad_connected(id));
+        assert!(input.gamepad_button_pressed(id, GamepadButton::South));
+        assert!(input.gamepad_button_just_pressed(id, GamepadButton::South));
+
+        // Inside the dead zones
+        assert_eq!(input.gamepad_stick(id, GamepadStick::Left), Vec2::ZERO);
+        assert_eq!(input.gamepad_axis(id, GamepadAxis::LeftTrigger), 0.0);
+        // Rescaled so the edge of the dead zone is zero and the end stays one
+        assert!((input.gamepad_axis(id, GamepadAxis::RightStickX) - 0.5).abs() < 1e-5);
+        assert_eq!(input.gamepad_axis(id, GamepadAxis::RightTrigger), 1.0);
+
+        input.tick(Duration::from_millis(16));
+        assert!(input.gamepad_button_pressed(id, GamepadButton::South));
+        assert!(!input.gamepad_button_just_pressed(id, GamepadButton::South));
+
+        // Pressing a held button again isn't a new press
+        backend.press(id, GamepadButton::South);
+        backend.release(id, GamepadButton::East);
+        input.poll_gamepads(&mut backend);
+        assert!(!input.gamepad_button_just_pressed(id, GamepadButton::South));
+        assert!(!input.gamepad_button_just_released(id, GamepadButton::East));
+
+        backend.release(id, GamepadButton::South);
+        input.poll_gamepads(&mut backend);
+        assert!(!input.gamepad_button_pressed(id, GamepadButton::South));
+        assert!(input.gamepad_button_just_released(id, GamepadButton::South));
+
+        input.set_gamepad_dead_zones(0.05, 0.05);
+        assert!(input.gamepad_stick(id, GamepadStick::Left).length() > 0.0);
+
+        backend.disconnect(id);
+        input.poll_gamepads(&mut backend);
+        assert!(!input.gamepad_connected(id));
+        assert!(!input.gamepad_button_pressed(id, GamepadButton::South));
+    }
+
+    #[test]
+    fn held_durations() {
+        let mut input = InputResource::new(true, PhysicalSize::new(800, 600));
+        let dt = Duration::from_millis(16);
+
+        input.input_event(InputEvent::Key(KeyCode::KeyW, ElementState::Pressed));
+        input.input_event(InputEvent::MouseButton(
+            MouseButton::Left,
+            ElementState::Pressed,
+        ));
+        input.tick(dt);
+        input.tick(dt);
+        assert_eq!(input.key_held_duration(KeyCode::KeyW), dt * 2);
+        assert_eq!(input.mouse_button_held_duration(MouseButton::Left), dt * 2);
+
+        input.input_event(InputEvent::Key(KeyCode::KeyW, ElementState::Released));
+        input.input_event(InputEvent::MouseButton(
+            MouseButton::Left,
+            ElementState::Released,
+        ));
+        assert_eq!(input.key_held_duration(KeyCode::KeyW), Duration::ZERO);
+        assert_eq!(input.mouse_button_held_duration(MouseButton::Left), Duration::ZERO);
+
+        input.tick(dt);
+        assert_eq!(input.key_held_duration(KeyCode::KeyW), Duration::ZERO);
+        assert_eq!(input.mouse_button_held_duration(MouseButton::Left), Duration::ZERO);
+    }
+
+    #[test]
+    fn text_skips_control_characters() {
+        let mut input = InputResource::new(true, PhysicalSize::new(800, 600));
+        input.start_text_input();
+
+        input.input_event(InputEvent::Text("a\u{8}".into()));
+        input.input_event(InputEvent::Ime(Ime::Commit("日本\r\n語".into())));
+        assert_eq!(input.get_text(), "a日本語");
+    }
+}
@@ -1,14 +1,29 @@
 use brood::Resources;
 use glam::Vec4;
 
-use self::{camera::CameraResource, input::InputResource, time::TimerResource};
+use self::{
+    anti_aliasing::AntiAliasingResource, input::InputResource, picking::PickingResource,
+    physics::PhysicsResource, post_process::PostProcessResource, time::TimerResource,
+};
 
-pub mod camera;
+pub mod anti_aliasing;
 pub mod input;
+pub mod physics;
+pub mod picking;
+pub mod post_process;
 pub mod time;
 
 pub struct ExitResource(pub bool);
 
 pub struct SunResource(pub Vec4);
 
-pub type Resources = Resources!(CameraResource, TimerResource, InputResource, ExitResource, SunResource);
+pub type Resources = Resources!(
+    TimerResource,
+    InputResource,
+    ExitResource,
+    SunResource,
+    PickingResource,
+    PostProcessResource,
+    AntiAliasingResource,
+    PhysicsResource
+);
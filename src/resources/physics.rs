@@ -0,0 +1,30 @@
+use glam::Vec3A;
+
+use crate::physics::query::PhysicsQuery;
+
+// Settings of the fixed physics step run by `PhysicsSystem`, and the colliders it left
+// for systems after it to query
+pub struct PhysicsResource {
+    pub gravity: Vec3A,
+    pub fixed_dt: f32,
+    // Solver passes over the contacts per step, more makes stacks steadier
+    pub iterations: u32,
+    // Time left over is dropped past this, so a slow frame doesn't make the next one slower
+    pub max_steps: u32,
+    // Frame time not yet stepped through
+    pub accumulator: f32,
+    pub query: PhysicsQuery,
+}
+
+impl Default for PhysicsResource {
+    fn default() -> Self {
+        Self {
+            gravity: Vec3A::new(0.0, -9.81, 0.0),
+            fixed_dt: 1.0 / 60.0,
+            iterations: 10,
+            max_steps: 5,
+            accumulator: 0.0,
+            query: PhysicsQuery::default(),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use crate::picking::PickHit;
+
+// What is under the cursor, updated each frame by `PickingSystem`
+#[derive(Default)]
+pub struct PickingResource {
+    pub hovered: Option<PickHit>,
+}
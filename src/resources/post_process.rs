@@ -0,0 +1,91 @@
+use std::{borrow::Cow, path::Path};
+
+use glam::Vec4;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ToneMapping {
+    None,
+    Reinhard,
+    Aces,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Bloom {
+    // Brightness above which pixels start to bleed
+    pub threshold: f32,
+    pub intensity: f32,
+    // Blur passes at half resolution, each one widens the glow
+    pub iterations: u32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            intensity: 0.5,
+            iterations: 4,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Vignette {
+    pub intensity: f32,
+    pub smoothness: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            intensity: 0.8,
+            smoothness: 0.5,
+        }
+    }
+}
+
+// A fullscreen fragment shader run on the HDR image before tone mapping. It gets
+// `sampler2D color`, `vec2 resolution`, `vec4 params` and `in vec2 frag_tex_coords`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct PostPass {
+    pub shader: Cow<'static, Path>,
+    pub params: Vec4,
+}
+
+impl PostPass {
+    pub fn new(shader: impl Into<Cow<'static, Path>>) -> Self {
+        Self {
+            shader: shader.into(),
+            params: Vec4::ZERO,
+        }
+    }
+}
+
+pub struct PostProcessResource {
+    pub exposure: f32,
+    pub tone_mapping: ToneMapping,
+    pub bloom: Option<Bloom>,
+    pub vignette: Option<Vignette>,
+    // Strip of blue slices, each a red/green square, e.g. 256x16 for a 16^3 LUT.
+    // Applied to the sRGB output after tone mapping.
+    pub color_grading_lut: Option<Cow<'static, Path>>,
+    pub passes: Vec<PostPass>,
+}
+
+impl Default for PostProcessResource {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            tone_mapping: ToneMapping::Aces,
+            bloom: None,
+            vignette: None,
+            color_grading_lut: None,
+            passes: Vec::new(),
+        }
+    }
+}
+
+impl PostProcessResource {
+    pub fn add_pass(&mut self, pass: PostPass) {
+        self.passes.push(pass);
+    }
+}
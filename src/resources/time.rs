@@ -34,4 +34,9 @@ impl TimerResource {
         self.dt = std::cmp::min(current - self.previous, self.max_duration);
         self.previous = current;
     }
+
+    pub fn tick_with(&mut self, dt: Duration) {
+        self.dt = dt;
+        self.previous = Instant::now();
+    }
 }
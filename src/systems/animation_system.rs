@@ -0,0 +1,46 @@
+use brood::{query::filter, result, system::System, Views};
+
+use crate::{
+    components::{
+        animation_player::AnimationPlayerComponent, morph_weights::MorphWeightsComponent,
+    },
+    resources::time::TimerResource,
+};
+
+pub struct AnimationSystem;
+
+impl System for AnimationSystem {
+    type Filter = filter::None;
+    type Views<'a> = Views!(
+        &'a mut AnimationPlayerComponent,
+        Option<&'a mut MorphWeightsComponent>
+    );
+    type ResourceViews<'a> = Views!(&'a TimerResource);
+    type EntryViews<'a> = Views!();
+
+    fn run<'a, R, S, I, E>(
+        &mut self,
+        query_result: brood::query::Result<
+            'a,
+            R,
+            S,
+            I,
+            Self::ResourceViews<'a>,
+            Self::EntryViews<'a>,
+            E,
+        >,
+    ) where
+        R: brood::registry::ContainsViews<'a, Self::EntryViews<'a>, E>,
+        I: Iterator<Item = Self::Views<'a>>,
+    {
+        let result!(timer) = query_result.resources;
+
+        for result!(player, morph_weights) in query_result.iter {
+            player.advance(timer.get_dt_f32());
+
+            if let Some(morph_weights) = morph_weights {
+                player.sample_morph_weights(&mut morph_weights.weights);
+            }
+        }
+    }
+}
@@ -1,25 +1,37 @@
 use std::f32::consts::PI;
 
 use brood::{
+    entity,
     query::{filter, result},
     system::System,
     Views,
 };
 use glam::{Mat3A, Quat, Vec3A};
-use winit::keyboard::KeyCode;
+use winit::{event::MouseButton, keyboard::KeyCode};
 
-use crate::resources::{camera::CameraResource, input::InputResource, time::TimerResource};
+use crate::{
+    components::{
+        camera::{CameraComponent, CameraController, FollowController, OrbitController},
+        transform::TransformComponent,
+    },
+    physics::query::PhysicsQuery,
+    resources::{input::InputResource, physics::PhysicsResource, time::TimerResource},
+};
 
 const CAMERA_SPEED: f32 = 5.0;
 const CAMERA_SENS: f32 = 0.001;
+const CAMERA_ZOOM_SENS: f32 = 0.1;
 
-#[derive(Default)]
 pub struct CameraSystem;
 
 impl System for CameraSystem {
     type Filter = filter::None;
-    type Views<'a> = Views!();
-    type ResourceViews<'a> = Views!(&'a mut CameraResource, &'a InputResource, &'a TimerResource);
+    type Views<'a> = Views!(
+        entity::Identifier,
+        &'a mut TransformComponent,
+        Option<&'a mut CameraComponent>
+    );
+    type ResourceViews<'a> = Views!(&'a InputResource, &'a TimerResource, &'a PhysicsResource);
     type EntryViews<'a> = Views!();
 
     fn run<'a, R, S, I, E>(
@@ -37,39 +49,157 @@ impl System for CameraSystem {
         R: brood::registry::ContainsViews<'a, Self::EntryViews<'a>, E>,
         I: Iterator<Item = Self::Views<'a>>,
     {
-        let result!(camera, input, timer) = query_result.resources;
+        let result!(input, timer, physics) = query_result.resources;
 
-        let (mut y, mut x, _) = camera.rotation.to_euler(glam::EulerRot::YXZ);
+        // Cameras and what they follow share a component type, so every position has to be
+        // known before any camera can be moved
+        let mut translations = Vec::new();
+        let mut cameras = Vec::new();
 
-        y = input.get_mouse_delta().x * CAMERA_SENS + y;
-        x = (input.get_mouse_delta().y * CAMERA_SENS + x).clamp(-PI/2.0, PI/2.0);
+        for result!(identifier, transform, camera) in query_result.iter {
+            translations.push((identifier, transform.translation));
+            if let Some(camera) = camera {
+                cameras.push((transform, camera));
+            }
+        }
 
-        camera.rotation = Quat::from_euler(glam::EulerRot::YXZ, y, x, 0.0);
+        for (transform, camera) in cameras {
+            if !camera.active {
+                continue;
+            }
 
-        let mut raw_dir = Vec3A::ZERO;
-        
-        if input.key_pressed(KeyCode::KeyD) {
-            raw_dir.x += 1.0;
-        }
-        if input.key_pressed(KeyCode::KeyE) {
-            raw_dir.y += 1.0;
-        }
-        if input.key_pressed(KeyCode::KeyW) {
-            raw_dir.z += 1.0;
-        }
+            let (translation, rotation) = match &mut camera.controller {
+                CameraController::Fixed => continue,
+                CameraController::FreeFly => {
+                    free_fly(transform.translation, transform.rotation, input, timer)
+                }
+                CameraController::Orbit(orbit) => orbit_camera(orbit, input),
+                CameraController::Follow(follow) => {
+                    let target = translations
+                        .iter()
+                        .find(|(entity, _)| *entity == follow.target);
+                    match target {
+                        Some((_, target)) => {
+                            follow_camera(follow, *target, &physics.query, input, timer)
+                        }
+                        None => (transform.translation, transform.rotation),
+                    }
+                }
+            };
 
-        if input.key_pressed(KeyCode::KeyA) {
-            raw_dir.x -= 1.0;
-        }
-        if input.key_pressed(KeyCode::KeyQ) {
-            raw_dir.y -= 1.0;
-        }
-        if input.key_pressed(KeyCode::KeyS) {
-            raw_dir.z -= 1.0;
+            transform.translation = translation;
+            transform.rotation = rotation;
         }
+    }
+}
+
+fn look(yaw: &mut f32, pitch: &mut f32, input: &InputResource) {
+    *yaw += input.get_mouse_delta().x * CAMERA_SENS;
+    *pitch = (input.get_mouse_delta().y * CAMERA_SENS + *pitch).clamp(-PI / 2.0, PI / 2.0);
+}
+
+fn zoom(distance: &mut f32, min: f32, max: f32, input: &InputResource) {
+    *distance =
+        (*distance * (1.0 - input.get_scroll_lines().y * CAMERA_ZOOM_SENS)).clamp(min, max);
+}
+
+fn free_fly(
+    translation: Vec3A,
+    rotation: Quat,
+    input: &InputResource,
+    timer: &TimerResource,
+) -> (Vec3A, Quat) {
+    let (mut y, mut x, _) = rotation.to_euler(glam::EulerRot::YXZ);
+
+    look(&mut y, &mut x, input);
+
+    let rotation = Quat::from_euler(glam::EulerRot::YXZ, y, x, 0.0);
+
+    let mut raw_dir = Vec3A::ZERO;
+
+    if input.key_pressed(KeyCode::KeyD) {
+        raw_dir.x += 1.0;
+    }
+    if input.key_pressed(KeyCode::KeyE) {
+        raw_dir.y += 1.0;
+    }
+    if input.key_pressed(KeyCode::KeyW) {
+        raw_dir.z += 1.0;
+    }
+
+    if input.key_pressed(KeyCode::KeyA) {
+        raw_dir.x -= 1.0;
+    }
+    if input.key_pressed(KeyCode::KeyQ) {
+        raw_dir.y -= 1.0;
+    }
+    if input.key_pressed(KeyCode::KeyS) {
+        raw_dir.z -= 1.0;
+    }
+
+    raw_dir = Mat3A::from_quat(rotation) * raw_dir;
 
-        raw_dir = Mat3A::from_quat(camera.rotation) * raw_dir;
-        
-        camera.translation += raw_dir.normalize_or_zero() * CAMERA_SPEED * timer.get_dt_f32();
+    (
+        translation + raw_dir.normalize_or_zero() * CAMERA_SPEED * timer.get_dt_f32(),
+        rotation,
+    )
+}
+
+fn orbit_camera(orbit: &mut OrbitController, input: &InputResource) -> (Vec3A, Quat) {
+    if input.mouse_button_pressed(MouseButton::Right) {
+        look(&mut orbit.yaw, &mut orbit.pitch, input);
     }
+    zoom(&mut orbit.distance, orbit.min_distance, orbit.max_distance, input);
+
+    let rotation = Quat::from_euler(glam::EulerRot::YXZ, orbit.yaw, orbit.pitch, 0.0);
+
+    (orbit.target - rotation * Vec3A::Z * orbit.distance, rotation)
+}
+
+fn follow_camera(
+    follow: &mut FollowController,
+    target: Vec3A,
+    query: &PhysicsQuery,
+    input: &InputResource,
+    timer: &TimerResource,
+) -> (Vec3A, Quat) {
+    look(&mut follow.yaw, &mut follow.pitch, input);
+    zoom(
+        &mut follow.arm_length,
+        follow.min_arm_length,
+        follow.max_arm_length,
+        input,
+    );
+
+    // Framerate independent exponential smoothing
+    let t = 1.0 - (-follow.stiffness * timer.get_dt_f32()).exp();
+
+    let pivot = target + follow.offset;
+    let current_pivot = follow.current_pivot.get_or_insert(pivot);
+    *current_pivot = current_pivot.lerp(pivot, t);
+
+    let rotation = Quat::from_euler(glam::EulerRot::YXZ, follow.yaw, follow.pitch, 0.0);
+
+    // Pulled in right away when something is in the way, so the camera never ends up behind
+    // it, and let out smoothly again
+    let arm_length = query
+        .sphere_cast_ignoring(
+            *current_pivot,
+            follow.probe_radius,
+            -(rotation * Vec3A::Z),
+            follow.arm_length,
+            follow.collision_mask,
+            follow.target,
+        )
+        .map_or(follow.arm_length, |hit| hit.distance);
+    if arm_length < follow.current_arm_length {
+        follow.current_arm_length = arm_length;
+    } else {
+        follow.current_arm_length += (arm_length - follow.current_arm_length) * t;
+    }
+
+    (
+        *current_pivot - rotation * Vec3A::Z * follow.current_arm_length,
+        rotation,
+    )
 }
@@ -0,0 +1,41 @@
+use brood::{query::filter, result, system::System, Views};
+
+use crate::{
+    components::{keyframe_animation::KeyframeAnimationComponent, transform::TransformComponent},
+    resources::time::TimerResource,
+};
+
+pub struct KeyframeAnimationSystem;
+
+impl System for KeyframeAnimationSystem {
+    type Filter = filter::None;
+    type Views<'a> = Views!(
+        &'a mut TransformComponent,
+        &'a mut KeyframeAnimationComponent
+    );
+    type ResourceViews<'a> = Views!(&'a TimerResource);
+    type EntryViews<'a> = Views!();
+
+    fn run<'a, R, S, I, E>(
+        &mut self,
+        query_result: brood::query::Result<
+            'a,
+            R,
+            S,
+            I,
+            Self::ResourceViews<'a>,
+            Self::EntryViews<'a>,
+            E,
+        >,
+    ) where
+        R: brood::registry::ContainsViews<'a, Self::EntryViews<'a>, E>,
+        I: Iterator<Item = Self::Views<'a>>,
+    {
+        let result!(timer) = query_result.resources;
+
+        for result!(transform, animation) in query_result.iter {
+            animation.advance(timer.get_dt_f32());
+            animation.apply(transform);
+        }
+    }
+}
@@ -1,3 +1,35 @@
+use brood::system::schedule::task;
+
+use self::{
+    animation_system::AnimationSystem, camera_system::CameraSystem, close_system::CloseSystem,
+    keyframe_animation_system::KeyframeAnimationSystem, physics_system::PhysicsSystem,
+    spin_system::SpinCube,
+};
+
+pub mod animation_system;
 pub mod camera_system;
 pub mod close_system;
+pub mod keyframe_animation_system;
+pub mod physics_system;
+pub mod picking_system;
 pub mod spin_system;
+
+pub type Schedule = brood::system::schedule::Schedule!(
+    task::System<SpinCube>,
+    task::System<KeyframeAnimationSystem>,
+    task::System<PhysicsSystem>,
+    task::System<AnimationSystem>,
+    task::System<CameraSystem>,
+    task::System<CloseSystem>,
+);
+
+pub fn schedule() -> Schedule {
+    brood::schedule!(
+        task::System(SpinCube),
+        task::System(KeyframeAnimationSystem),
+        task::System(PhysicsSystem),
+        task::System(AnimationSystem),
+        task::System(CameraSystem),
+        task::System(CloseSystem),
+    )
+}
@@ -0,0 +1,100 @@
+use brood::{entity, query::filter, result, system::System, Views};
+
+use crate::{
+    components::{
+        collider::ColliderComponent, rigid_body::RigidBodyComponent, transform::TransformComponent,
+    },
+    physics::{
+        query::{PhysicsQuery, QueryCollider},
+        shape::WorldShape,
+        solver::{step, PhysicsBody},
+    },
+    resources::{physics::PhysicsResource, time::TimerResource},
+};
+
+// Steps the simulation at a fixed rate, as many times as the frame time covers. The
+// colliders are then snapshot for queries every frame, so ones moved by other systems show up.
+pub struct PhysicsSystem;
+
+impl System for PhysicsSystem {
+    type Filter = filter::Or<filter::Has<RigidBodyComponent>, filter::Has<ColliderComponent>>;
+    type Views<'a> = Views!(
+        entity::Identifier,
+        &'a mut TransformComponent,
+        Option<&'a mut RigidBodyComponent>,
+        Option<&'a ColliderComponent>
+    );
+    type ResourceViews<'a> = Views!(&'a mut PhysicsResource, &'a TimerResource);
+    type EntryViews<'a> = Views!();
+
+    fn run<'a, R, S, I, E>(
+        &mut self,
+        query_result: brood::query::Result<
+            'a,
+            R,
+            S,
+            I,
+            Self::ResourceViews<'a>,
+            Self::EntryViews<'a>,
+            E,
+        >,
+    ) where
+        R: brood::registry::ContainsViews<'a, Self::EntryViews<'a>, E>,
+        I: Iterator<Item = Self::Views<'a>>,
+    {
+        let result!(physics, timer) = query_result.resources;
+
+        physics.accumulator += timer.get_dt_f32();
+        let steps = ((physics.accumulator / physics.fixed_dt) as u32).min(physics.max_steps);
+        // What couldn't be caught up on is dropped, but a partial step is kept
+        physics.accumulator =
+            (physics.accumulator - steps as f32 * physics.fixed_dt).min(physics.fixed_dt);
+
+        let (entities, mut bodies): (Vec<_>, Vec<_>) = query_result
+            .iter
+            .map(|result!(entity, transform, rigid_body, collider)| {
+                (
+                    entity,
+                    PhysicsBody {
+                        transform,
+                        rigid_body,
+                        collider,
+                    },
+                )
+            })
+            .unzip();
+
+        if steps > 0 {
+            for _ in 0..steps {
+                step(
+                    &mut bodies,
+                    physics.gravity,
+                    physics.iterations,
+                    physics.fixed_dt,
+                );
+            }
+
+            for rigid_body in bodies
+                .iter_mut()
+                .filter_map(|body| body.rigid_body.as_mut())
+            {
+                rigid_body.force = glam::Vec3A::ZERO;
+                rigid_body.torque = glam::Vec3A::ZERO;
+            }
+        }
+
+        physics.query = PhysicsQuery::new(
+            entities
+                .into_iter()
+                .zip(&bodies)
+                .filter_map(|(entity, body)| {
+                    body.collider.map(|collider| QueryCollider {
+                        entity,
+                        shape: WorldShape::new(&collider.shape, body.transform),
+                        layers: collider.layers,
+                    })
+                })
+                .collect(),
+        );
+    }
+}
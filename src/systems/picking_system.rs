@@ -0,0 +1,65 @@
+use brood::{entity, query::filter, result, system::System, Views};
+
+use crate::{
+    components::{camera::CameraComponent, draw::DrawComponent, transform::TransformComponent},
+    picking::raycast_drawables,
+    resources::{input::InputResource, picking::PickingResource},
+};
+
+// Reads `DrawComponent`, which isn't `Sync`, so this is run outside of the schedule
+pub struct PickingSystem;
+
+impl System for PickingSystem {
+    type Filter = filter::None;
+    type Views<'a> = Views!(
+        entity::Identifier,
+        &'a TransformComponent,
+        Option<&'a CameraComponent>,
+        Option<&'a DrawComponent>
+    );
+    type ResourceViews<'a> = Views!(&'a InputResource, &'a mut PickingResource);
+    type EntryViews<'a> = Views!();
+
+    fn run<'a, R, S, I, E>(
+        &mut self,
+        query_result: brood::query::Result<
+            'a,
+            R,
+            S,
+            I,
+            Self::ResourceViews<'a>,
+            Self::EntryViews<'a>,
+            E,
+        >,
+    ) where
+        R: brood::registry::ContainsViews<'a, Self::EntryViews<'a>, E>,
+        I: Iterator<Item = Self::Views<'a>>,
+    {
+        let result!(input, picking) = query_result.resources;
+
+        let mut cameras = Vec::new();
+        let mut drawables = Vec::new();
+
+        for result!(entity, transform, camera, draw) in query_result.iter {
+            if let Some(camera) = camera {
+                // Only cameras drawn to the window can be under the cursor
+                if camera.active && camera.target.is_none() {
+                    cameras.push((transform, camera));
+                }
+            }
+            if let Some(draw) = draw {
+                drawables.push((entity, transform, draw));
+            }
+        }
+
+        // The camera drawn last is the one visible where viewports overlap
+        cameras.sort_by_key(|(_, camera)| -camera.order);
+
+        let ray = cameras.iter().find_map(|(transform, camera)| {
+            camera.screen_to_ray(transform, input.get_cursor_pos(), input.get_window_size())
+        });
+
+        picking.hovered =
+            ray.and_then(|ray| raycast_drawables(&ray, f32::INFINITY, drawables));
+    }
+}
@@ -1,11 +1,24 @@
 use brood::{query::filter, result, system::System, Views};
 use glam::Quat;
 
-use crate::{components::transform::TransformComponent, resources::time::TimerResource};
+use crate::{
+    components::{
+        camera::CameraComponent, keyframe_animation::KeyframeAnimationComponent,
+        rigid_body::RigidBodyComponent, transform::TransformComponent,
+    },
+    resources::time::TimerResource,
+};
 pub struct SpinCube;
 
 impl System for SpinCube {
-    type Filter = filter::None;
+    // Keyframe animated and physics entities are left to their animation and simulation
+    type Filter = filter::And<
+        filter::Not<filter::Has<CameraComponent>>,
+        filter::And<
+            filter::Not<filter::Has<KeyframeAnimationComponent>>,
+            filter::Not<filter::Has<RigidBodyComponent>>,
+        >,
+    >;
     type Views<'a> = Views!(&'a mut TransformComponent);
     type ResourceViews<'a> = Views!(&'a TimerResource);
     type EntryViews<'a> = Views!();